@@ -1,4 +1,3 @@
-use anyhow::anyhow;
 use async_trait::async_trait;
 use rdkafka::{
     admin::{
@@ -12,10 +11,15 @@ use rdkafka::{
 use tracing::{error, info};
 
 use super::KafkaAdminInterface;
+use crate::{
+    errors::KafkaError,
+    security::{rdkafka_impl::apply_security_config, SecurityConfig},
+    KafkaResult,
+};
 
 #[async_trait]
 impl KafkaAdminInterface for AdminClient<DefaultClientContext> {
-    async fn verify_topic_existence(&self, topic: &str) -> anyhow::Result<bool> {
+    async fn verify_topic_existence(&self, topic: &str) -> KafkaResult<bool> {
         let topic_config = ResourceSpecifier::Topic(topic);
         let admin_options =
             AdminOptions::new().request_timeout(Some(std::time::Duration::from_secs(1)));
@@ -45,7 +49,7 @@ impl KafkaAdminInterface for AdminClient<DefaultClientContext> {
             })
             .map_err(|e| {
                 error!("Error describing topic: {:?}", e);
-                anyhow!(e.to_string())
+                KafkaError::from(e)
             })
     }
     async fn create_topic_if_not_exists(
@@ -53,7 +57,7 @@ impl KafkaAdminInterface for AdminClient<DefaultClientContext> {
         topic: &str,
         partitions: i32,
         replication_factor: i32,
-    ) -> anyhow::Result<()> {
+    ) -> KafkaResult<()> {
         let topic_exists = self.verify_topic_existence(topic).await?;
         if !topic_exists {
             let new_topic = NewTopic::new(
@@ -79,12 +83,12 @@ impl KafkaAdminInterface for AdminClient<DefaultClientContext> {
             })
             .map_err(|e| {
                 error!("Error creating topic: {:?}", e);
-                anyhow!(e.to_string())
+                KafkaError::from(e)
             })?;
         }
         Ok(())
     }
-    async fn check_topic_liveness(&self, topic: &str) -> anyhow::Result<bool> {
+    async fn check_topic_liveness(&self, topic: &str) -> KafkaResult<bool> {
         let topic_config = ResourceSpecifier::Topic(topic);
         let admin_options = AdminOptions::new();
         self.describe_configs(vec![&topic_config], &admin_options)
@@ -109,7 +113,7 @@ impl KafkaAdminInterface for AdminClient<DefaultClientContext> {
             })
             .map_err(|e| {
                 error!("Error describing topic: {:?}", e);
-                anyhow!(e.to_string())
+                KafkaError::from(e)
             })
     }
 
@@ -117,16 +121,17 @@ impl KafkaAdminInterface for AdminClient<DefaultClientContext> {
         bootstrap_servers: String,
         request_time_out_ms: String,
         connection_max_idle_ms: String,
-    ) -> Self {
-        AdminClient::from_config(
-            &rdkafka::ClientConfig::new()
-                .set("bootstrap.servers", bootstrap_servers)
-                .set("request.timeout.ms", &request_time_out_ms)
-                .set(
-                    "connections.max.idle.ms",
-                    &connection_max_idle_ms.to_string(),
-                ),
-        )
-        .expect("Failed to create admin client")
+        security: SecurityConfig,
+    ) -> KafkaResult<Self> {
+        let mut client_config = rdkafka::ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", bootstrap_servers)
+            .set("request.timeout.ms", &request_time_out_ms)
+            .set("connections.max.idle.ms", connection_max_idle_ms);
+        apply_security_config(&security, &mut client_config)?;
+        AdminClient::from_config(&client_config).map_err(|e| {
+            error!("Failed to create admin client: {:?}", e);
+            KafkaError::from(e)
+        })
     }
 }
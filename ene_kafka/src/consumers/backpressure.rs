@@ -0,0 +1,122 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use tokio::sync::Notify;
+
+///
+/// Pauses all the partitions of the consumer while it is overloaded, and resumes them once it has drained.
+/// The consumer keeps polling while its partitions are paused, so it stays in its group.
+/// The consumer is overloaded once one of the thresholds is reached, and drained once all of them are back under their resume level.
+///
+/// Example:
+/// ```rust,ignore
+/// let downstream_pressure = PressureSignal::new(Thresholds::new(1000, 200));
+/// let consumer = kafka_consumer!(
+///     ...,
+///     handlers = {
+///         entity_created_handler: EntityCreatedHandler = EntityCreatedHandler { pressure: downstream_pressure.clone() }
+///     }
+/// )?
+/// .with_concurrency(Concurrency::Partition { max_in_flight: 256 })
+/// .with_backpressure(
+///     Backpressure::default()
+///         .with_max_in_flight(Thresholds::new(128, 32))
+///         .with_signal(downstream_pressure),
+/// );
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct Backpressure {
+    /// The number of events received but not processed yet, including the ones buffered until there is room for them, see `Concurrency`
+    pub max_in_flight: Option<Thresholds>,
+    /// A pressure reported by the handlers, e.g. the queue depth of a downstream system
+    pub signal: Option<PressureSignal>,
+}
+
+impl Backpressure {
+    pub fn with_max_in_flight(mut self, max_in_flight: Thresholds) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    pub fn with_signal(mut self, signal: PressureSignal) -> Self {
+        self.signal = Some(signal);
+        self
+    }
+}
+
+/// The level at which a pressure pauses the consumer, and the level it must drain to before the consumer is resumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub pause_at: u64,
+    pub resume_at: u64,
+}
+
+impl Thresholds {
+    /// `resume_at` is lowered to `pause_at` if it is higher
+    pub fn new(pause_at: u64, resume_at: u64) -> Self {
+        Self {
+            pause_at,
+            resume_at: resume_at.min(pause_at),
+        }
+    }
+
+    /// Returns true if the pressure is too high, given whether it was too high before
+    pub(crate) fn is_exceeded(&self, level: u64, was_exceeded: bool) -> bool {
+        if was_exceeded {
+            level > self.resume_at
+        } else {
+            level >= self.pause_at
+        }
+    }
+}
+
+///
+/// A pressure that handlers report to the consumer, to pause it while a downstream system is overloaded.
+/// Signals are cheap to clone: the consumer and the handlers share the same level.
+///
+#[derive(Debug, Clone)]
+pub struct PressureSignal {
+    inner: Arc<SignalState>,
+}
+
+#[derive(Debug)]
+struct SignalState {
+    thresholds: Thresholds,
+    level: AtomicU64,
+    changed: Notify,
+}
+
+impl PressureSignal {
+    pub fn new(thresholds: Thresholds) -> Self {
+        Self {
+            inner: Arc::new(SignalState {
+                thresholds,
+                level: AtomicU64::new(0),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    /// Reports the current pressure
+    pub fn report(&self, level: u64) {
+        self.inner.level.store(level, Ordering::Relaxed);
+        self.inner.changed.notify_one();
+    }
+
+    /// Returns the last reported pressure
+    pub fn level(&self) -> u64 {
+        self.inner.level.load(Ordering::Relaxed)
+    }
+
+    pub fn thresholds(&self) -> Thresholds {
+        self.inner.thresholds
+    }
+
+    /// Waits until a pressure is reported
+    pub(crate) async fn changed(&self) {
+        self.inner.changed.notified().await
+    }
+}
@@ -0,0 +1,95 @@
+use std::{future::Future, time::Duration};
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::concurrency::TrackedOffset;
+
+///
+/// When the consumer commits the offsets of the events it has processed.
+/// Committing more often narrows the window of events that are consumed again after a crash or a rebalance,
+/// committing less often increases the throughput.
+/// Whatever the strategy, an offset is only committed once all the events before it in its partition have been processed,
+/// and the offsets of the processed events are committed when the consumer shuts down.
+///
+/// Example:
+/// ```rust,ignore
+/// let consumer = kafka_consumer!(...)?.with_commit_strategy(CommitStrategy::Batched {
+///     max_events: 500,
+///     interval: Duration::from_secs(1),
+/// });
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CommitStrategy {
+    /// Commits after every processed event, without waiting for the broker to acknowledge the commit
+    #[default]
+    PerEventAsync,
+    /// Commits after every processed event and waits for the broker to acknowledge the commit
+    PerEventSync,
+    /// Commits once `max_events` events were processed, or `interval` after the first event processed since the last commit.
+    /// The pending offsets of the partitions revoked in a rebalance are committed before the partitions are given away.
+    Batched {
+        max_events: usize,
+        interval: Duration,
+    },
+    /// Stores the offsets of the processed events and lets the underlying client commit them in the background,
+    /// every `auto.commit.interval.ms` (5 seconds by default, see `ConsumerConfig::with_property`)
+    Auto,
+    /// Only commits the events that the handlers acknowledged, see `Acknowledgement`.
+    /// Events that failed and were sent to a retry topic or the dead letter queue are acknowledged by the consumer.
+    Manual,
+}
+
+/// An event or a batch of events acknowledged by a handler
+pub(crate) type AcknowledgedOffsets = Vec<TrackedOffset>;
+
+tokio::task_local! {
+    static ACKNOWLEDGEMENT: Acknowledgement;
+}
+
+///
+/// A handle to acknowledge the events being handled when the consumer uses `CommitStrategy::Manual`.
+/// Their offsets are committed once they, and all the events before them in their partition, are acknowledged.
+/// Events that are never acknowledged hold back the commits of their partition and are consumed again after a restart.
+///
+/// Example:
+/// ```rust,ignore
+/// async fn handle_entity_created(&self, event: &EntityCreated) -> ene_kafka::KafkaResult<()> {
+///     let acknowledgement = Acknowledgement::current();
+///     self.jobs.send((event.clone(), acknowledgement)).await?;
+///     Ok(())
+/// }
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Acknowledgement {
+    offsets: AcknowledgedOffsets,
+    sender: UnboundedSender<AcknowledgedOffsets>,
+}
+
+impl Acknowledgement {
+    pub(crate) fn new(
+        offsets: AcknowledgedOffsets,
+        sender: UnboundedSender<AcknowledgedOffsets>,
+    ) -> Self {
+        Self { offsets, sender }
+    }
+
+    /// Returns the acknowledgement of the event, or the batch of events, being handled.
+    /// Returns `None` outside of a handler or if the consumer does not use `CommitStrategy::Manual`.
+    /// It must be called from the handler itself, the acknowledgement can then be moved to other tasks.
+    pub fn current() -> Option<Self> {
+        ACKNOWLEDGEMENT.try_with(Clone::clone).ok()
+    }
+
+    /// Marks the events as processed. Acknowledging them more than once has no effect.
+    pub fn ack(self) {
+        // The consumer has shut down if the channel is closed, the events are consumed again after a restart
+        let _ = self.sender.send(self.offsets);
+    }
+
+    /// Makes this acknowledgement available to the handlers called by `future`
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        ACKNOWLEDGEMENT.scope(self, future).await
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+///
+/// How many events the consumer processes at the same time.
+/// Events that share a partition, or a key in `Concurrency::Key` mode, are processed in order, one after the other.
+/// Whatever the mode, an offset is only committed once all the events before it in its partition have been processed.
+///
+/// Example:
+/// ```rust,ignore
+/// let consumer = kafka_consumer!(...)?.with_concurrency(Concurrency::Partition { max_in_flight: 64 });
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Concurrency {
+    /// Events are processed one at a time, across all partitions
+    #[default]
+    Sequential,
+    /// Events of different partitions are processed concurrently.
+    /// At most `max_in_flight` events are dispatched but not yet processed at any time,
+    /// the consumer buffers the events it receives beyond that and pauses its partitions while the buffer is full.
+    Partition { max_in_flight: usize },
+    /// Events with different keys are processed concurrently, even within a partition,
+    /// while events with the same key are processed in order. Events without a key are processed in order within their partition.
+    /// At most `max_in_flight` events are dispatched but not yet processed at any time,
+    /// the consumer buffers the events it receives beyond that and pauses its partitions while the buffer is full.
+    Key { max_in_flight: usize },
+}
+
+impl Concurrency {
+    /// The maximum number of events dispatched but not yet processed
+    pub fn max_in_flight(&self) -> usize {
+        match self {
+            Concurrency::Sequential => 1,
+            Concurrency::Partition { max_in_flight } | Concurrency::Key { max_in_flight } => {
+                (*max_in_flight).max(1)
+            }
+        }
+    }
+}
+
+/// Events of the same lane are processed in order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Lane {
+    pub(crate) topic: String,
+    pub(crate) partition: i32,
+    /// Only set when events are ordered by key
+    pub(crate) key: Option<Vec<u8>>,
+    /// The assignment of the partition the events were received in, see `OffsetTracker::track`.
+    /// Events received before the partition was revoked never hold back the ones received after it was assigned again.
+    pub(crate) epoch: u64,
+}
+
+/// The offset of an event, along with the assignment of its partition the event was received in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TrackedOffset {
+    pub(crate) topic: String,
+    pub(crate) partition: i32,
+    pub(crate) offset: i64,
+    pub(crate) epoch: u64,
+}
+
+/// Orders the processing of events that share a lane, while events of different lanes are processed concurrently
+#[derive(Debug)]
+pub(crate) struct Lanes<Event> {
+    queued: HashMap<Lane, VecDeque<Event>>,
+    busy: HashSet<Lane>,
+}
+
+impl<Event> Default for Lanes<Event> {
+    fn default() -> Self {
+        Self {
+            queued: HashMap::new(),
+            busy: HashSet::new(),
+        }
+    }
+}
+
+impl<Event> Lanes<Event> {
+    /// Returns the event if its lane is free and it can be processed right away, otherwise queues it behind the lane
+    pub(crate) fn push(&mut self, lane: Lane, event: Event) -> Option<Event> {
+        if self.busy.contains(&lane) {
+            self.queued.entry(lane).or_default().push_back(event);
+            None
+        } else {
+            self.busy.insert(lane);
+            Some(event)
+        }
+    }
+
+    /// Frees the lane once its current event is processed and returns the next event to process in that lane, if any
+    pub(crate) fn complete(&mut self, lane: &Lane) -> Option<Event> {
+        let next = self.queued.get_mut(lane).and_then(VecDeque::pop_front);
+        if next.is_none() {
+            self.queued.remove(lane);
+            self.busy.remove(lane);
+        }
+        next
+    }
+
+    /// Drops the lanes of a revoked partition and returns the number of queued events that were dropped
+    pub(crate) fn forget(&mut self, topic: &str, partition: i32) -> usize {
+        let is_revoked = |lane: &Lane| lane.topic == topic && lane.partition == partition;
+        self.busy.retain(|lane| !is_revoked(lane));
+        let mut dropped = 0;
+        self.queued.retain(|lane, events| {
+            if is_revoked(lane) {
+                dropped += events.len();
+            }
+            !is_revoked(lane)
+        });
+        dropped
+    }
+}
+
+/// Keeps track of the events that were received but not yet processed,
+/// to only commit offsets that all the events before them in their partition have been processed for.
+#[derive(Debug, Default)]
+pub(crate) struct OffsetTracker {
+    partitions: HashMap<(String, i32), PartitionOffsets>,
+    last_epoch: u64,
+}
+
+#[derive(Debug, Default)]
+struct PartitionOffsets {
+    epoch: u64,
+    pending: BTreeSet<i64>,
+    next: i64,
+    committable: Option<i64>,
+}
+
+impl OffsetTracker {
+    /// Tracks a received event and returns the epoch of its partition, which changes every time
+    /// the partition is tracked again after it was forgotten, i.e. assigned again after it was revoked.
+    pub(crate) fn track(&mut self, topic: &str, partition: i32, offset: i64) -> u64 {
+        let last_epoch = &mut self.last_epoch;
+        let partition_offsets = self
+            .partitions
+            .entry((topic.to_string(), partition))
+            .or_insert_with(|| {
+                *last_epoch += 1;
+                PartitionOffsets {
+                    epoch: *last_epoch,
+                    ..PartitionOffsets::default()
+                }
+            });
+        partition_offsets.pending.insert(offset);
+        partition_offsets.next = partition_offsets.next.max(offset + 1);
+        partition_offsets.epoch
+    }
+
+    /// Marks the event as processed and returns the offset that can now be committed for its partition,
+    /// if it moved forward. Events received in a previous epoch of their partition are ignored.
+    pub(crate) fn complete(
+        &mut self,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        epoch: u64,
+    ) -> Option<i64> {
+        let partition_offsets = self
+            .partitions
+            .get_mut(&(topic.to_string(), partition))
+            .filter(|partition_offsets| partition_offsets.epoch == epoch)?;
+        partition_offsets.pending.remove(&offset);
+        let committable = partition_offsets
+            .pending
+            .first()
+            .copied()
+            .unwrap_or(partition_offsets.next);
+        if partition_offsets.committable == Some(committable) {
+            return None;
+        }
+        partition_offsets.committable = Some(committable);
+        Some(committable)
+    }
+
+    /// Stops tracking a partition, so that no offset is committed for it until it is tracked again
+    pub(crate) fn forget(&mut self, topic: &str, partition: i32) {
+        self.partitions.remove(&(topic.to_string(), partition));
+    }
+
+    /// Returns the offsets that can be committed for every partition an event has been processed for
+    pub(crate) fn committable_offsets(&self) -> Vec<(&str, i32, i64)> {
+        self.partitions
+            .iter()
+            .filter_map(|((topic, partition), partition_offsets)| {
+                partition_offsets
+                    .committable
+                    .map(|offset| (topic.as_str(), *partition, offset))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(partition: i32, key: Option<&[u8]>, epoch: u64) -> Lane {
+        Lane {
+            topic: "orders".to_string(),
+            partition,
+            key: key.map(<[u8]>::to_vec),
+            epoch,
+        }
+    }
+
+    #[test]
+    fn commits_offsets_once_all_earlier_events_are_completed() {
+        let mut offsets = OffsetTracker::default();
+        let epoch = offsets.track("orders", 0, 10);
+        offsets.track("orders", 0, 11);
+        offsets.track("orders", 0, 12);
+
+        assert_eq!(offsets.complete("orders", 0, 12, epoch), Some(10));
+        assert_eq!(offsets.complete("orders", 0, 11, epoch), None);
+        assert_eq!(offsets.complete("orders", 0, 10, epoch), Some(13));
+        assert_eq!(offsets.committable_offsets(), vec![("orders", 0, 13)]);
+    }
+
+    #[test]
+    fn tracks_partitions_independently() {
+        let mut offsets = OffsetTracker::default();
+        let first_epoch = offsets.track("orders", 0, 10);
+        let second_epoch = offsets.track("orders", 1, 20);
+        offsets.track("orders", 1, 21);
+
+        assert_eq!(offsets.complete("orders", 1, 20, second_epoch), Some(21));
+        assert_eq!(offsets.complete("orders", 0, 10, first_epoch), Some(11));
+    }
+
+    #[test]
+    fn forgets_revoked_partitions() {
+        let mut offsets = OffsetTracker::default();
+        let epoch = offsets.track("orders", 0, 10);
+        offsets.complete("orders", 0, 10, epoch);
+        offsets.track("orders", 0, 11);
+        offsets.forget("orders", 0);
+
+        assert_eq!(offsets.complete("orders", 0, 11, epoch), None);
+        assert!(offsets.committable_offsets().is_empty());
+    }
+
+    #[test]
+    fn ignores_completions_from_a_previous_assignment() {
+        let mut offsets = OffsetTracker::default();
+        let revoked_epoch = offsets.track("orders", 0, 10);
+        offsets.forget("orders", 0);
+        let epoch = offsets.track("orders", 0, 10);
+        offsets.track("orders", 0, 11);
+
+        assert_ne!(revoked_epoch, epoch);
+        assert_eq!(offsets.complete("orders", 0, 10, revoked_epoch), None);
+        assert_eq!(offsets.complete("orders", 0, 11, epoch), Some(10));
+        assert_eq!(offsets.complete("orders", 0, 10, epoch), Some(12));
+    }
+
+    #[test]
+    fn processes_events_of_a_lane_in_order() {
+        let mut lanes = Lanes::default();
+        assert_eq!(lanes.push(lane(0, None, 1), 10), Some(10));
+        assert_eq!(lanes.push(lane(0, None, 1), 11), None);
+        assert_eq!(lanes.push(lane(0, None, 1), 12), None);
+        assert_eq!(lanes.push(lane(1, None, 1), 20), Some(20));
+
+        assert_eq!(lanes.complete(&lane(0, None, 1)), Some(11));
+        assert_eq!(lanes.complete(&lane(0, None, 1)), Some(12));
+        assert_eq!(lanes.complete(&lane(0, None, 1)), None);
+        assert_eq!(lanes.push(lane(0, None, 1), 13), Some(13));
+    }
+
+    #[test]
+    fn processes_events_with_different_keys_concurrently() {
+        let mut lanes = Lanes::default();
+        assert_eq!(lanes.push(lane(0, Some(b"a"), 1), 10), Some(10));
+        assert_eq!(lanes.push(lane(0, Some(b"b"), 1), 11), Some(11));
+        assert_eq!(lanes.push(lane(0, Some(b"a"), 1), 12), None);
+
+        assert_eq!(lanes.complete(&lane(0, Some(b"b"), 1)), None);
+        assert_eq!(lanes.complete(&lane(0, Some(b"a"), 1)), Some(12));
+    }
+
+    #[test]
+    fn drops_the_lanes_of_revoked_partitions() {
+        let mut lanes = Lanes::default();
+        lanes.push(lane(0, None, 1), 10);
+        lanes.push(lane(0, None, 1), 11);
+        lanes.push(lane(0, None, 1), 12);
+        lanes.push(lane(1, None, 1), 20);
+        lanes.push(lane(1, None, 1), 21);
+
+        assert_eq!(lanes.forget("orders", 0), 2);
+        assert_eq!(lanes.complete(&lane(0, None, 1)), None);
+        assert_eq!(lanes.complete(&lane(1, None, 1)), Some(21));
+    }
+
+    #[test]
+    fn does_not_hold_back_events_of_a_new_assignment() {
+        let mut lanes = Lanes::default();
+        assert_eq!(lanes.push(lane(0, None, 1), 10), Some(10));
+        lanes.forget("orders", 0);
+        assert_eq!(lanes.push(lane(0, None, 2), 10), Some(10));
+        assert_eq!(lanes.push(lane(0, None, 2), 11), None);
+
+        // The event of the revoked assignment completes after the partition was assigned again
+        assert_eq!(lanes.complete(&lane(0, None, 1)), None);
+        assert_eq!(lanes.complete(&lane(0, None, 2)), Some(11));
+    }
+}
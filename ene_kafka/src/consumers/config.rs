@@ -0,0 +1,223 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::{producers::config::ProducerConfig, security::SecurityConfig};
+
+/// What the consumer should do when there is no committed offset for a partition,
+/// or when the committed offset is out of range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoOffsetReset {
+    Earliest,
+    Latest,
+    Error,
+}
+
+impl AutoOffsetReset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Earliest => "earliest",
+            Self::Latest => "latest",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Controls which messages of transactional producers are visible to the consumer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+}
+
+impl IsolationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadUncommitted => "read_uncommitted",
+            Self::ReadCommitted => "read_committed",
+        }
+    }
+}
+
+/// How the partitions of the subscribed topics are distributed among the consumers of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionAssignmentStrategy {
+    Range,
+    RoundRobin,
+    /// Only moves the partitions that change owner in a rebalance, the other consumers keep consuming meanwhile.
+    /// All the consumers of the group must use it.
+    CooperativeSticky,
+}
+
+impl PartitionAssignmentStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Range => "range",
+            Self::RoundRobin => "roundrobin",
+            Self::CooperativeSticky => "cooperative-sticky",
+        }
+    }
+}
+
+/// The most verbose logs of the underlying Kafka client that are forwarded to the application logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogLevel {
+    Emerg,
+    Alert,
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    #[default]
+    Debug,
+}
+
+///
+/// Tuning options of a Kafka consumer.
+/// Every option that is left empty falls back to the default of the underlying Kafka client.
+/// `properties` are passed as-is to the underlying client and take precedence over the typed options.
+///
+/// Example:
+/// ```rust,ignore
+/// let config = ConsumerConfig::default()
+///     .with_auto_offset_reset(AutoOffsetReset::Earliest)
+///     .with_max_poll_interval(Duration::from_secs(600))
+///     .with_property("fetch.wait.max.ms", "100");
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct ConsumerConfig {
+    pub auto_offset_reset: Option<AutoOffsetReset>,
+    pub session_timeout: Option<Duration>,
+    pub heartbeat_interval: Option<Duration>,
+    pub max_poll_interval: Option<Duration>,
+    pub fetch_min_bytes: Option<u32>,
+    pub fetch_max_bytes: Option<u32>,
+    pub max_partition_fetch_bytes: Option<u32>,
+    pub fetch_max_wait: Option<Duration>,
+    pub isolation_level: Option<IsolationLevel>,
+    pub client_id: Option<String>,
+    pub partition_assignment_strategy: Option<PartitionAssignmentStrategy>,
+    /// Makes the consumer a static member of its group, see `with_group_instance_id`
+    pub group_instance_id: Option<String>,
+    /// Defaults to `LogLevel::Debug`, the logs are then filtered by the application's subscriber
+    pub log_level: LogLevel,
+    /// The config of the producer that sends the failed events to the retry topics and the dead letter queue,
+    /// see `with_dlq_producer_config`
+    pub dlq_producer: Option<ProducerConfig>,
+    pub security: SecurityConfig,
+    pub properties: HashMap<String, String>,
+}
+
+impl Default for ConsumerConfig {
+    fn default() -> Self {
+        Self {
+            auto_offset_reset: None,
+            session_timeout: Some(Duration::from_millis(6000)),
+            heartbeat_interval: None,
+            max_poll_interval: None,
+            fetch_min_bytes: None,
+            fetch_max_bytes: None,
+            max_partition_fetch_bytes: None,
+            fetch_max_wait: None,
+            isolation_level: None,
+            client_id: None,
+            partition_assignment_strategy: None,
+            group_instance_id: None,
+            log_level: LogLevel::default(),
+            dlq_producer: None,
+            security: SecurityConfig::default(),
+            properties: HashMap::new(),
+        }
+    }
+}
+
+impl ConsumerConfig {
+    pub fn with_auto_offset_reset(mut self, auto_offset_reset: AutoOffsetReset) -> Self {
+        self.auto_offset_reset = Some(auto_offset_reset);
+        self
+    }
+
+    pub fn with_session_timeout(mut self, session_timeout: Duration) -> Self {
+        self.session_timeout = Some(session_timeout);
+        self
+    }
+
+    pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.heartbeat_interval = Some(heartbeat_interval);
+        self
+    }
+
+    pub fn with_max_poll_interval(mut self, max_poll_interval: Duration) -> Self {
+        self.max_poll_interval = Some(max_poll_interval);
+        self
+    }
+
+    pub fn with_fetch_min_bytes(mut self, fetch_min_bytes: u32) -> Self {
+        self.fetch_min_bytes = Some(fetch_min_bytes);
+        self
+    }
+
+    pub fn with_fetch_max_bytes(mut self, fetch_max_bytes: u32) -> Self {
+        self.fetch_max_bytes = Some(fetch_max_bytes);
+        self
+    }
+
+    pub fn with_max_partition_fetch_bytes(mut self, max_partition_fetch_bytes: u32) -> Self {
+        self.max_partition_fetch_bytes = Some(max_partition_fetch_bytes);
+        self
+    }
+
+    pub fn with_fetch_max_wait(mut self, fetch_max_wait: Duration) -> Self {
+        self.fetch_max_wait = Some(fetch_max_wait);
+        self
+    }
+
+    pub fn with_isolation_level(mut self, isolation_level: IsolationLevel) -> Self {
+        self.isolation_level = Some(isolation_level);
+        self
+    }
+
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn with_partition_assignment_strategy(
+        mut self,
+        partition_assignment_strategy: PartitionAssignmentStrategy,
+    ) -> Self {
+        self.partition_assignment_strategy = Some(partition_assignment_strategy);
+        self
+    }
+
+    /// Makes the consumer a static member of its group. The id must be unique within the group and stable across restarts,
+    /// e.g. the name of the pod. A static member that restarts within the session timeout gets its partitions back
+    /// without a rebalance of the group.
+    pub fn with_group_instance_id(mut self, group_instance_id: impl Into<String>) -> Self {
+        self.group_instance_id = Some(group_instance_id.into());
+        self
+    }
+
+    pub fn with_log_level(mut self, log_level: LogLevel) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// Tunes the producer that sends the failed events to the retry topics and the dead letter queue.
+    /// By default, it uses the default `ProducerConfig` with the security of the consumer.
+    pub fn with_dlq_producer_config(mut self, dlq_producer: ProducerConfig) -> Self {
+        self.dlq_producer = Some(dlq_producer);
+        self
+    }
+
+    pub fn with_security(mut self, security: SecurityConfig) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Sets a raw property on the underlying Kafka client, e.g. `fetch.wait.max.ms`
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+}
@@ -1,21 +1,47 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
 
+use crate::admins::KafkaAdminInterface;
+use crate::consumers::backpressure::Backpressure;
+use crate::consumers::commit::CommitStrategy;
+use crate::consumers::concurrency::Concurrency;
+use crate::consumers::config::ConsumerConfig;
+use crate::consumers::handle::ConsumerHandle;
+use crate::consumers::options::{BatchConfig, ConsumerOptions, StartOffset};
+use crate::consumers::rebalance::RebalanceListener;
+use crate::consumers::retry::RetryPolicy;
+use crate::consumers::retry_topics::RetryTopics;
+use crate::consumers::subscription::Subscription;
+use crate::dispatchers::middleware::DispatchMiddleware;
 use crate::dispatchers::EventDispatcher;
+use crate::dlq::DlqFailureMode;
+use crate::errors::KafkaError;
 use crate::messages::kafka_message::KafkaTopic;
+use crate::producers::config::ProducerConfig;
 use crate::producers::producer::{KafkaProducer, KafkaProducerInterface};
-use crate::{ConsumerImpl, ProducerImpl};
+use crate::{ConsumerImpl, KafkaResult, ProducerImpl};
 
 #[async_trait]
 pub trait KafkaConsumerInterface<Dispatcher: EventDispatcher, InnerProducer: KafkaProducerInterface>
 {
-    fn new(consumer_group_id: String, bootstrap_servers: String) -> Self;
+    fn new(
+        consumer_group_id: String,
+        bootstrap_servers: String,
+        config: ConsumerConfig,
+    ) -> KafkaResult<Self>
+    where
+        Self: Sized;
     async fn start<'a>(
         &'a self,
         dispatcher: &'a Dispatcher,
         dlq_producer: &'a KafkaProducer<InnerProducer>,
-        topic: KafkaTopic,
+        subscription: Subscription,
         dlq_topic: KafkaTopic,
-    );
+        options: &'a ConsumerOptions,
+        handle: ConsumerHandle,
+    ) -> KafkaResult<()>;
 }
 
 #[derive(Debug, Clone)]
@@ -24,11 +50,13 @@ pub struct KafkaConsumer<
     InnerConsumer: KafkaConsumerInterface<Dispatcher, InnerProducer> = ConsumerImpl,
     InnerProducer: KafkaProducerInterface = ProducerImpl,
 > {
-    topic: KafkaTopic,
+    subscription: Subscription,
     dlq_topic: KafkaTopic,
     dispatcher: Dispatcher,
     inner_consumer: InnerConsumer,
     dlq_producer: KafkaProducer<InnerProducer>,
+    options: ConsumerOptions,
+    handle: ConsumerHandle,
 }
 
 impl<
@@ -38,47 +66,174 @@ impl<
     > KafkaConsumer<Dispatcher, Consumer, InnerProducer>
 {
     pub fn new(
-        topic: KafkaTopic,
+        subscription: impl Into<Subscription>,
         dlq_topic: KafkaTopic,
         consumer_group_id: String,
         bootstrap_servers: String,
+        config: ConsumerConfig,
         handler: Dispatcher,
-    ) -> Self {
-        let dlq_producer = KafkaProducer::new(bootstrap_servers.clone());
-        Self {
-            topic,
+    ) -> KafkaResult<Self> {
+        let dlq_producer_config = config
+            .dlq_producer
+            .clone()
+            .unwrap_or_else(|| ProducerConfig::default().with_security(config.security.clone()));
+        let dlq_producer = KafkaProducer::new(bootstrap_servers.clone(), dlq_producer_config)?;
+        Ok(Self {
+            subscription: subscription.into(),
             dlq_topic,
             dispatcher: handler,
-            inner_consumer: Consumer::new(consumer_group_id, bootstrap_servers),
+            inner_consumer: Consumer::new(consumer_group_id.clone(), bootstrap_servers, config)?,
             dlq_producer,
+            options: ConsumerOptions {
+                consumer_group_id,
+                ..ConsumerOptions::default()
+            },
+            handle: ConsumerHandle::default(),
+        })
+    }
+
+    /// Sets the policy used to retry events in place before they are sent to the dead letter queue
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.options.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the retry topics that failed events are sent through before they reach the dead letter queue.
+    /// The consumer subscribes to the retry topics as well and dispatches their events once they are due.
+    pub fn with_retry_topics(mut self, retry_topics: RetryTopics) -> Self {
+        self.options.retry_topics = Some(retry_topics);
+        self
+    }
+
+    /// Sends the failed events of `topic` to `dlq_topic` instead of the dead letter queue of the consumer
+    pub fn with_topic_dlq(mut self, topic: impl Into<String>, dlq_topic: KafkaTopic) -> Self {
+        self.options.dlq_topics.insert(topic.into(), dlq_topic);
+        self
+    }
+
+    /// Sets what happens when a failed event cannot be sent to its retry topic or to the dead letter queue.
+    /// Use `DlqFailureMode::block()` to never commit the offset of an event that was not forwarded.
+    pub fn with_dlq_failure_mode(mut self, dlq_failure_mode: DlqFailureMode) -> Self {
+        self.options.dlq_failure_mode = dlq_failure_mode;
+        self
+    }
+
+    /// Sets how many events the consumer processes at the same time. Events are processed one at a time by default.
+    pub fn with_concurrency(mut self, concurrency: Concurrency) -> Self {
+        self.options.concurrency = concurrency;
+        self
+    }
+
+    /// Collects events into batches before dispatching them, so that batch handlers can handle them in bulk.
+    /// Events that are not handled by a batch handler are dispatched one at a time within their batch.
+    pub fn with_batching(mut self, batch_config: BatchConfig) -> Self {
+        self.options.batching = Some(batch_config);
+        self
+    }
+
+    /// Sets when the offsets of the processed events are committed. Offsets are committed after every event by default.
+    pub fn with_commit_strategy(mut self, commit_strategy: CommitStrategy) -> Self {
+        self.options.commit_strategy = commit_strategy;
+        self
+    }
+
+    /// Registers a listener that is notified when partitions are assigned to the consumer or taken away from it
+    pub fn with_rebalance_listener(mut self, listener: impl RebalanceListener + 'static) -> Self {
+        self.options.rebalance_listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Sets where the consumer starts reading the partitions assigned to it. It resumes from the committed offsets by default.
+    pub fn with_start_offset(mut self, start_offset: StartOffset) -> Self {
+        self.options.start_offset = start_offset;
+        self
+    }
+
+    /// Pauses the partitions of the consumer while it is overloaded, and resumes them once it has drained
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.options.backpressure = Some(backpressure);
+        self
+    }
+
+    /// Fails the dispatch of an event, or of a batch of events, with `KafkaError::HandlerTimeout` if it takes longer than `timeout`.
+    /// Timed out events go through the retry policy and the dead letter queue like any other failure.
+    /// Handlers can set a timeout of their own as well, see `EventHandler::timeout`.
+    pub fn with_handler_timeout(mut self, timeout: Duration) -> Self {
+        self.options.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// Runs the dispatch of every event through the middleware, inside the middlewares added before it
+    pub fn with_middleware(mut self, middleware: impl DispatchMiddleware + 'static) -> Self {
+        self.options.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Creates the retry topics of this consumer if they do not exist yet.
+    /// Returns an error for pattern subscriptions, whose retry topics are not known up front.
+    pub async fn provision_retry_topics<Admin: KafkaAdminInterface + Sync>(
+        &self,
+        admin: &Admin,
+    ) -> KafkaResult<()> {
+        let Some(retry_topics) = &self.options.retry_topics else {
+            return Ok(());
+        };
+        match &self.subscription {
+            Subscription::Topics(topics) => {
+                for topic in topics {
+                    retry_topics.provision(admin, topic).await?;
+                }
+                Ok(())
+            }
+            Subscription::Pattern { pattern, .. } => Err(KafkaError::Config(format!(
+                "The retry topics of the topics matching {} must be created explicitly",
+                pattern
+            ))),
         }
     }
 
+    /// Returns a handle that can be used to pause, resume or shut the consumer down gracefully
+    /// once it has been started.
+    pub fn handle(&self) -> ConsumerHandle {
+        self.handle.clone()
+    }
+
     /// Starts the consumer loop
-    /// This function will block the current thread
-    /// It will consume messages from the Kafka topic and dispatch them to the handlers.
-    /// If the message could not be consumed, it will be sent to the dead letter queue.
-    pub async fn start(self) {
+    /// This function will block the current thread until a shutdown is requested through a `ConsumerHandle`.
+    /// It will consume messages from the subscribed Kafka topics and dispatch them to the handlers.
+    /// If the message could not be consumed, it is retried according to the retry policy
+    /// and then sent to the dead letter queue.
+    /// Returns an error if the consumer could not subscribe to the topics, move its partitions to the start offset
+    /// or hold back an event of a retry topic until it is due, or `KafkaError::Fatal` if a handler stopped it with `HandlerOutcome::Fatal`.
+    pub async fn start(self) -> KafkaResult<()> {
         self.inner_consumer
             .start(
                 &self.dispatcher,
                 &self.dlq_producer,
-                self.topic,
+                self.subscription,
                 self.dlq_topic,
+                &self.options,
+                self.handle,
             )
-            .await;
+            .await
     }
 }
 
 ///
 /// Create a new Kafka consumer
+/// Returns an error if the consumer or its dead letter queue producer could not be created.
 /// Arguments:
-/// - `topic` - a string representing the Kafka topic
+/// - `topic` - a `KafkaTopic` to consume from. Use `topics` instead to pass a list of topics or a `Subscription` to a regex pattern.
+///   `topics = [topic => dlq_topic, ...]` consumes from a list of topics that each send their failed events to their own dead letter queue, see `with_topic_dlq`.
 /// - `dlq_topic` - a string representing the Kafka dead letter queue topic. If an event could not be consuler, it will be sent to the dead letter queue.
 /// - `consumer_group_id` - a string representing the Kafka consumer group id
 /// - `bootstrap_servers` - a string representing the Kafka bootstrap servers
+/// - `config` - (optional) a `ConsumerConfig` to tune the consumer and its dead letter queue producer. The defaults are used if omitted.
 /// - `handlers` - a list of handle declarations that will be used by this consumer
-/// The handlers need to implement The `EventHandler` trait.
+/// - `batch_handlers` - (optional) a list of handle declarations that receive the events they can handle in batches, see `with_batching`
+/// - `service` - a `tower::Service` of `CloudEventRequest`s to dispatch the events to, instead of `handlers`. Requires the `tower` feature, see `ServiceDispatcher`.
+///
+/// The handlers need to implement The `EventHandler` trait, and the batch handlers the `BatchEventHandler` trait.
 ///
 /// Example:
 /// ```rust,ignore
@@ -93,35 +248,145 @@ impl<
 ///        },
 ///        consumer_group_id = "test-group",
 ///        bootstrap_servers = bootstrap_servers,
+///        config = ConsumerConfig::default().with_auto_offset_reset(AutoOffsetReset::Earliest),
 ///        handlers = {
 ///            entity_created_event_handler: EntityCreatedEventHandler = EntityCreatedEventHandler {}
 ///        }
-///    );
-/// consumer.start().await;
+///    )?;
+/// consumer.start().await?;
+///
+///    let consumer = kafka_consumer!(
+///        topics = Subscription::pattern("^tenant-.*-orders$", ContentType::Json),
+///        dlq_topic = KafkaTopic {
+///            name: "orders-dlq".to_string(),
+///            content_type: ContentType::Json
+///        },
+///        consumer_group_id = "orders-group",
+///        bootstrap_servers = bootstrap_servers,
+///        handlers = {
+///            order_created_event_handler: OrderCreatedEventHandler = OrderCreatedEventHandler {}
+///        }
+///    )?;
+///
+///    let consumer = kafka_consumer!(
+///        topics = [orders_topic => orders_dlq_topic, payments_topic => payments_dlq_topic],
+///        dlq_topic = fallback_dlq_topic,
+///        consumer_group_id = "billing-group",
+///        bootstrap_servers = bootstrap_servers,
+///        handlers = {
+///            order_created_event_handler: OrderCreatedEventHandler = OrderCreatedEventHandler {}
+///        }
+///    )?;
 /// ```
 ///
 #[macro_export]
 macro_rules! kafka_consumer {
+    (topics = [$($topic: expr => $topic_dlq: expr),+ $(,)?], $($rest: tt)*) => {
+        {
+            let topic_dlqs: Vec<(
+                ene_kafka::messages::kafka_message::KafkaTopic,
+                ene_kafka::messages::kafka_message::KafkaTopic,
+            )> = vec![$(($topic, $topic_dlq)),+];
+            let topics = topic_dlqs
+                .iter()
+                .map(|(topic, _)| topic.clone())
+                .collect::<Vec<_>>();
+            ene_kafka::kafka_consumer!(topic = topics, $($rest)*).map(|consumer| {
+                topic_dlqs
+                    .into_iter()
+                    .fold(consumer, |consumer, (topic, topic_dlq)| {
+                        consumer.with_topic_dlq(topic.name, topic_dlq)
+                    })
+            })
+        }
+    };
+    (topics = $topics: expr, $($rest: tt)*) => {
+        ene_kafka::kafka_consumer!(topic = $topics, $($rest)*)
+    };
+    (
+        topic = $topic: expr,
+        dlq_topic = $dlq_topic: expr,
+        consumer_group_id = $consumer_group_id: expr,
+        bootstrap_servers = $bootstrap_servers: expr,
+        handlers = {$($handler_name: ident: $handler_type: ident = $handler: expr),*}$(,)?
+        $(batch_handlers = {$($batch_handler_name: ident: $batch_handler_type: ident = $batch_handler: expr),*}$(,)?)?
+        $(,)?
+    ) => {
+        ene_kafka::kafka_consumer!(
+            topic = $topic,
+            dlq_topic = $dlq_topic,
+            consumer_group_id = $consumer_group_id,
+            bootstrap_servers = $bootstrap_servers,
+            config = ene_kafka::consumers::config::ConsumerConfig::default(),
+            handlers = {$($handler_name: $handler_type = $handler),*},
+            batch_handlers = {$($($batch_handler_name: $batch_handler_type = $batch_handler),*)?}
+        )
+    };
+    (
+        topic = $topic: expr,
+        dlq_topic = $dlq_topic: expr,
+        consumer_group_id = $consumer_group_id: expr,
+        bootstrap_servers = $bootstrap_servers: expr,
+        service = $service: expr$(,)?
+    ) => {
+        ene_kafka::kafka_consumer!(
+            topic = $topic,
+            dlq_topic = $dlq_topic,
+            consumer_group_id = $consumer_group_id,
+            bootstrap_servers = $bootstrap_servers,
+            config = ene_kafka::consumers::config::ConsumerConfig::default(),
+            service = $service
+        )
+    };
+    (
+        topic = $topic: expr,
+        dlq_topic = $dlq_topic: expr,
+        consumer_group_id = $consumer_group_id: expr,
+        bootstrap_servers = $bootstrap_servers: expr,
+        config = $config: expr,
+        service = $service: expr$(,)?
+    ) => {
+        ene_kafka::consumers::consumer::KafkaConsumer::<ene_kafka::dispatchers::service::ServiceDispatcher<_>>::new(
+            $topic,
+            $dlq_topic,
+            $consumer_group_id.to_string(),
+            $bootstrap_servers.to_string(),
+            $config,
+            ene_kafka::dispatchers::service::ServiceDispatcher::new($service),
+        )
+    };
     (
         topic = $topic: expr,
         dlq_topic = $dlq_topic: expr,
         consumer_group_id = $consumer_group_id: expr,
         bootstrap_servers = $bootstrap_servers: expr,
+        config = $config: expr,
         handlers = {$($handler_name: ident: $handler_type: ident = $handler: expr),*}$(,)?
+        $(batch_handlers = {$($batch_handler_name: ident: $batch_handler_type: ident = $batch_handler: expr),*}$(,)?)?
         $(,)?
     ) => {
         {
 
-            ene_kafka::generate_event_dispatcher!($($handler_name: $handler_type),*);
+            ene_kafka::generate_event_dispatcher!(
+                handlers = {$($handler_name: $handler_type),*},
+                batch_handlers = {$($($batch_handler_name: $batch_handler_type),*)?}
+            );
 
 
-            ene_kafka::consumers::consumer::KafkaConsumer::<CloudEventDispatcher>::new(
-                $topic,
-                $dlq_topic,
-                $consumer_group_id.to_string(),
-                $bootstrap_servers.to_string(),
-                CloudEventDispatcher { $($handler_name: $handler),* }
+            CloudEventDispatcher::new(
+                $($handler,)*
+                $($($batch_handler,)*)?
             )
+            .and_then(|dispatcher| {
+                ene_kafka::consumers::consumer::KafkaConsumer::<CloudEventDispatcher>::new(
+                    $topic,
+                    $dlq_topic,
+                    $consumer_group_id.to_string(),
+                    $bootstrap_servers.to_string(),
+                    $config,
+                    dispatcher,
+                )
+            })
 
         }
 
@@ -0,0 +1,108 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use super::rebalance::TopicPartition;
+
+/// A handle to control a running `KafkaConsumer` from outside of its consumer loop.
+/// Handles are cheap to clone and can be moved to other tasks, e.g. a signal listener.
+///
+/// Example:
+/// ```rust,ignore
+/// let handle = consumer.handle();
+/// tokio::spawn(async move {
+///     tokio::signal::ctrl_c().await.unwrap();
+///     handle.shutdown();
+/// });
+/// consumer.start().await;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConsumerHandle {
+    shutdown: CancellationToken,
+    pauses: Arc<PauseRequests>,
+}
+
+#[derive(Debug, Default)]
+struct PauseRequests {
+    requested: Mutex<RequestedPauses>,
+    changed: Notify,
+}
+
+/// The partitions paused through a `ConsumerHandle`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequestedPauses {
+    pub(crate) all: bool,
+    pub(crate) partitions: HashSet<TopicPartition>,
+}
+
+impl ConsumerHandle {
+    /// Requests the consumer to stop.
+    /// The consumer stops polling, lets the event that is currently being dispatched finish,
+    /// commits its final offsets synchronously and leaves the consumer group.
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Returns true if a shutdown has been requested
+    pub fn is_shutdown_requested(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Stops fetching events from all the partitions assigned to the consumer, including the ones assigned later,
+    /// until `resume` is called. The consumer stays in its group, and the events that were already fetched are still processed.
+    pub fn pause(&self) {
+        self.update_pauses(|requested| requested.all = true);
+    }
+
+    /// Resumes all the partitions paused through this handle
+    pub fn resume(&self) {
+        self.update_pauses(|requested| *requested = RequestedPauses::default());
+    }
+
+    /// Stops fetching events from the partitions until they are resumed
+    pub fn pause_partitions(&self, partitions: &[TopicPartition]) {
+        self.update_pauses(|requested| requested.partitions.extend(partitions.iter().cloned()));
+    }
+
+    /// Resumes partitions paused by `pause_partitions`. Use `resume` to undo `pause`.
+    pub fn resume_partitions(&self, partitions: &[TopicPartition]) {
+        self.update_pauses(|requested| {
+            for partition in partitions {
+                requested.partitions.remove(partition);
+            }
+        });
+    }
+
+    /// Returns true if all the partitions are paused by `pause`
+    pub fn is_paused(&self) -> bool {
+        self.requested_pauses().all
+    }
+
+    pub(crate) fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    pub(crate) fn requested_pauses(&self) -> RequestedPauses {
+        self.pauses
+            .requested
+            .lock()
+            .map(|requested| requested.clone())
+            .unwrap_or_default()
+    }
+
+    /// Waits until a pause or a resume is requested
+    pub(crate) async fn pauses_changed(&self) {
+        self.pauses.changed.notified().await
+    }
+
+    fn update_pauses(&self, update: impl FnOnce(&mut RequestedPauses)) {
+        if let Ok(mut requested) = self.pauses.requested.lock() {
+            update(&mut requested);
+        }
+        self.pauses.changed.notify_one();
+    }
+}
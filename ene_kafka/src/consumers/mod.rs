@@ -1,2 +1,12 @@
+pub mod backpressure;
+pub mod commit;
+pub mod concurrency;
+pub mod config;
 pub mod consumer;
+pub mod handle;
+pub mod options;
 pub mod rdkafka_impl;
+pub mod rebalance;
+pub mod retry;
+pub mod retry_topics;
+pub mod subscription;
@@ -0,0 +1,91 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    dispatchers::middleware::DispatchMiddleware, dlq::DlqFailureMode,
+    messages::kafka_message::KafkaTopic,
+};
+
+use super::{
+    backpressure::Backpressure,
+    commit::CommitStrategy,
+    concurrency::Concurrency,
+    rebalance::{RebalanceListener, TopicPartition},
+    retry::RetryPolicy,
+    retry_topics::RetryTopics,
+};
+
+/// Settings that control how the consumer loop processes events,
+/// as opposed to `ConsumerConfig` which configures the underlying Kafka client.
+#[derive(Debug, Clone, Default)]
+pub struct ConsumerOptions {
+    /// The group id of the consumer, reported in the metadata of dead-lettered events
+    pub consumer_group_id: String,
+    pub retry_policy: RetryPolicy,
+    pub retry_topics: Option<RetryTopics>,
+    pub dlq_failure_mode: DlqFailureMode,
+    /// The dead letter queues of the topics that do not use the dead letter queue of the consumer, by topic name
+    pub dlq_topics: HashMap<String, KafkaTopic>,
+    pub concurrency: Concurrency,
+    pub batching: Option<BatchConfig>,
+    pub commit_strategy: CommitStrategy,
+    pub rebalance_listener: Option<Arc<dyn RebalanceListener>>,
+    pub start_offset: StartOffset,
+    pub backpressure: Option<Backpressure>,
+    /// How long a dispatch may take, on top of the timeouts of the handlers
+    pub handler_timeout: Option<Duration>,
+    /// The middlewares every dispatch runs through, the first one being the outermost
+    pub middlewares: Vec<Arc<dyn DispatchMiddleware>>,
+}
+
+///
+/// How the consumer collects events into batches before it dispatches them, see `BatchEventHandler`.
+/// A batch is dispatched once it holds `max_size` events, or `max_wait` after its first event was received.
+/// Batches are dispatched one at a time, the `Concurrency` of the consumer does not apply to them.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchConfig {
+    pub max_size: usize,
+    pub max_wait: Duration,
+}
+
+impl BatchConfig {
+    pub fn new(max_size: usize, max_wait: Duration) -> Self {
+        Self {
+            max_size: max_size.max(1),
+            max_wait,
+        }
+    }
+}
+
+///
+/// Where the consumer starts reading the partitions assigned to it, e.g. to rebuild a read model
+/// by replaying a topic through the same handlers.
+/// It applies to the first assignment of every partition to the consumer: partitions that are assigned again after
+/// a rebalance resume from their committed offset. Retry topics always resume from their committed offset.
+/// Use a new consumer group, or a single consumer, to replay a topic exactly once.
+/// If the partitions cannot be moved to the start offset, e.g. because the offsets of the timestamp
+/// cannot be looked up, the consumer stops and `KafkaConsumer::start` returns the error.
+///
+/// Example:
+/// ```rust,ignore
+/// let replay_from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let consumer = kafka_consumer!(...)?.with_start_offset(StartOffset::Timestamp(replay_from));
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StartOffset {
+    /// Resumes from the committed offsets, or from `auto.offset.reset` if there are none
+    #[default]
+    Committed,
+    /// Starts from the first event of every partition
+    Earliest,
+    /// Starts after the last event of every partition
+    Latest,
+    /// Starts the listed partitions at the given offsets, and resumes the others from their committed offsets
+    Offsets(HashMap<TopicPartition, i64>),
+    /// Starts from the first event of every partition that was published at or after the timestamp,
+    /// or after the last event of the partition if there is none
+    Timestamp(DateTime<Utc>),
+}
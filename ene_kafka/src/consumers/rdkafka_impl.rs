@@ -1,78 +1,1395 @@
 use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use rdkafka::client::ClientContext;
 use rdkafka::config::RDKafkaLogLevel;
-use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
-use rdkafka::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, OwnedMessage};
+use rdkafka::{ClientConfig, Message, Offset, TopicPartitionList};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
-use crate::dispatchers::EventDispatcher;
-use crate::messages::kafka_message::KafkaTopic;
+use crate::dispatchers::{middleware::MiddlewareDispatcher, EventDispatcher, TimeoutDispatcher};
+use crate::dlq::{DeadLetterMetadata, DlqFailureMode};
+use crate::messages::kafka_message::{self, KafkaTopic};
 use crate::producers::producer::{KafkaProducer, KafkaProducerInterface};
+use crate::security::rdkafka_impl::apply_security_config;
+use crate::{errors::KafkaError, KafkaResult};
 
+use super::backpressure::{Backpressure, Thresholds};
+use super::commit::{AcknowledgedOffsets, Acknowledgement, CommitStrategy};
+use super::concurrency::{Concurrency, Lane, Lanes, OffsetTracker, TrackedOffset};
+use super::config::{ConsumerConfig, LogLevel};
 use super::consumer::KafkaConsumerInterface;
+use super::handle::ConsumerHandle;
+use super::options::{ConsumerOptions, StartOffset};
+use super::rebalance::{RebalanceListener, TopicPartition};
+use super::retry::{dispatch_with_retry, retry_failed_dispatch, FailedDispatch, RetryPolicy};
+use super::retry_topics::{
+    retry_after, retry_attempt, RetryTopics, RETRY_AFTER_HEADER, RETRY_ATTEMPT_HEADER,
+    RETRY_ORIGINAL_TOPIC_HEADER,
+};
+use super::subscription::Subscription;
+
+const OFFSETS_FOR_TIMES_TIMEOUT: Duration = Duration::from_secs(10);
+const SEEK_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) fn consumer_client_config(
+    consumer_group_id: String,
+    bootstrap_servers: String,
+    config: &ConsumerConfig,
+) -> KafkaResult<ClientConfig> {
+    let mut client_config = ClientConfig::new();
+    client_config
+        .set("group.id", consumer_group_id)
+        .set("bootstrap.servers", bootstrap_servers)
+        .set("enable.partition.eof", "false")
+        // Offsets are only stored once their event has been processed, see `CommitStrategy::Auto`
+        .set("enable.auto.offset.store", "false");
+    if let Some(auto_offset_reset) = &config.auto_offset_reset {
+        client_config.set("auto.offset.reset", auto_offset_reset.as_str());
+    }
+    if let Some(session_timeout) = config.session_timeout {
+        client_config.set(
+            "session.timeout.ms",
+            session_timeout.as_millis().to_string(),
+        );
+    }
+    if let Some(heartbeat_interval) = config.heartbeat_interval {
+        client_config.set(
+            "heartbeat.interval.ms",
+            heartbeat_interval.as_millis().to_string(),
+        );
+    }
+    if let Some(max_poll_interval) = config.max_poll_interval {
+        client_config.set(
+            "max.poll.interval.ms",
+            max_poll_interval.as_millis().to_string(),
+        );
+    }
+    if let Some(fetch_min_bytes) = config.fetch_min_bytes {
+        client_config.set("fetch.min.bytes", fetch_min_bytes.to_string());
+    }
+    if let Some(fetch_max_bytes) = config.fetch_max_bytes {
+        client_config.set("fetch.max.bytes", fetch_max_bytes.to_string());
+    }
+    if let Some(max_partition_fetch_bytes) = config.max_partition_fetch_bytes {
+        client_config.set(
+            "max.partition.fetch.bytes",
+            max_partition_fetch_bytes.to_string(),
+        );
+    }
+    if let Some(fetch_max_wait) = config.fetch_max_wait {
+        client_config.set("fetch.wait.max.ms", fetch_max_wait.as_millis().to_string());
+    }
+    if let Some(isolation_level) = &config.isolation_level {
+        client_config.set("isolation.level", isolation_level.as_str());
+    }
+    if let Some(client_id) = &config.client_id {
+        client_config.set("client.id", client_id);
+    }
+    if let Some(partition_assignment_strategy) = &config.partition_assignment_strategy {
+        client_config.set(
+            "partition.assignment.strategy",
+            partition_assignment_strategy.as_str(),
+        );
+    }
+    if let Some(group_instance_id) = &config.group_instance_id {
+        client_config.set("group.instance.id", group_instance_id);
+    }
+    client_config.set_log_level(rdkafka_log_level(config.log_level));
+    apply_security_config(&config.security, &mut client_config)?;
+    for (key, value) in config.properties.iter() {
+        client_config.set(key, value);
+    }
+    Ok(client_config)
+}
+
+fn rdkafka_log_level(log_level: LogLevel) -> RDKafkaLogLevel {
+    match log_level {
+        LogLevel::Emerg => RDKafkaLogLevel::Emerg,
+        LogLevel::Alert => RDKafkaLogLevel::Alert,
+        LogLevel::Critical => RDKafkaLogLevel::Critical,
+        LogLevel::Error => RDKafkaLogLevel::Error,
+        LogLevel::Warning => RDKafkaLogLevel::Warning,
+        LogLevel::Notice => RDKafkaLogLevel::Notice,
+        LogLevel::Info => RDKafkaLogLevel::Info,
+        LogLevel::Debug => RDKafkaLogLevel::Debug,
+    }
+}
+
+/// The context of the consumers. It notifies the rebalance listener of the consumer around the default rebalance
+/// of the underlying client, and holds the partitions assigned for the first time until the consumer loop
+/// has moved them to the start offset of the consumer.
+#[derive(Default)]
+pub struct RebalanceContext {
+    /// The consumer of the context, set once it is created
+    consumer: OnceLock<Weak<StreamConsumer<RebalanceContext>>>,
+    listener: RwLock<Option<Arc<dyn RebalanceListener>>>,
+    /// The partitions revoked since the consumer loop last checked
+    revoked: Mutex<Vec<TopicPartition>>,
+    /// True if partitions were assigned since the consumer loop last checked
+    assigned: AtomicBool,
+    /// Wakes the consumer loop up once partitions are assigned or revoked
+    rebalanced: Notify,
+    /// The offsets that are not committed yet with `CommitStrategy::Batched`, by topic and partition
+    pending_offsets: Mutex<HashMap<(String, i32), i64>>,
+    /// True while the consumer loop pauses every partition, newly assigned partitions are then paused right away
+    pause_assigned: AtomicBool,
+    /// The partitions paused as soon as they were assigned, that the consumer loop has not taken over yet
+    paused_on_assign: Mutex<Vec<TopicPartition>>,
+    start: Mutex<StartPosition>,
+}
+
+/// Where the partitions assigned for the first time start
+#[derive(Default)]
+struct StartPosition {
+    start_offset: StartOffset,
+    /// The partitions of retry topics resume from their committed offsets
+    retry_topics: Option<RetryTopics>,
+    /// The partitions that were assigned before
+    started: HashSet<TopicPartition>,
+    /// The partitions that are paused until they are moved to the start offset
+    pending: Vec<TopicPartition>,
+}
+
+impl RebalanceContext {
+    fn consumer(&self) -> Option<Arc<StreamConsumer<RebalanceContext>>> {
+        self.consumer.get()?.upgrade()
+    }
+
+    fn set_listener(&self, listener: Option<Arc<dyn RebalanceListener>>) {
+        match self.listener.write() {
+            Ok(mut current_listener) => *current_listener = listener,
+            Err(error) => {
+                tracing::error!("consumers::rdkafka_impl::set_listener::error: {:?}", error)
+            }
+        }
+    }
+
+    fn listener(&self) -> Option<Arc<dyn RebalanceListener>> {
+        self.listener.read().ok()?.clone()
+    }
+
+    fn set_start_offset(&self, start_offset: StartOffset, retry_topics: Option<RetryTopics>) {
+        match self.start.lock() {
+            Ok(mut start) => {
+                start.start_offset = start_offset;
+                start.retry_topics = retry_topics;
+            }
+            Err(error) => {
+                tracing::error!(
+                    "consumers::rdkafka_impl::set_start_offset::error: {:?}",
+                    error
+                )
+            }
+        }
+    }
+
+    /// Pauses the partitions that are assigned for the first time and do not start from their committed offsets,
+    /// so that none of their events is received before the consumer loop moves them to the start offset
+    fn hold_until_started(&self, partitions: &[TopicPartition]) {
+        let Ok(mut start) = self.start.lock() else {
+            return;
+        };
+        let StartPosition {
+            start_offset,
+            retry_topics,
+            started,
+            pending,
+        } = &mut *start;
+        let mut holding = TopicPartitionList::new();
+        for partition in partitions {
+            let is_retry_topic = retry_topics.as_ref().is_some_and(|retry_topics| {
+                retry_topics.original_topic(&partition.topic).is_some()
+            });
+            if is_retry_topic || !started.insert(partition.clone()) {
+                continue;
+            }
+            let has_start_offset = match start_offset {
+                StartOffset::Committed => false,
+                StartOffset::Offsets(offsets) => offsets.contains_key(partition),
+                StartOffset::Earliest | StartOffset::Latest | StartOffset::Timestamp(_) => true,
+            };
+            if has_start_offset {
+                holding.add_partition(&partition.topic, partition.partition);
+                pending.push(partition.clone());
+            }
+        }
+        if holding.count() == 0 {
+            return;
+        }
+        if let Some(Err(error)) = self.consumer().map(|consumer| consumer.pause(&holding)) {
+            tracing::error!("consumers::rdkafka_impl::hold::error: {:?}", error);
+        }
+    }
+
+    /// Pauses the newly assigned partitions while the consumer loop pauses every partition,
+    /// so that none of their events is received before the consumer loop takes them over
+    fn pause_if_paused(&self, partitions: &TopicPartitionList) {
+        if !self.pause_assigned.load(Ordering::Relaxed) {
+            return;
+        }
+        match self.consumer().map(|consumer| consumer.pause(partitions)) {
+            Some(Ok(())) => {
+                if let Ok(mut paused_on_assign) = self.paused_on_assign.lock() {
+                    paused_on_assign.extend(topic_partitions(partitions));
+                }
+            }
+            Some(Err(error)) => {
+                tracing::error!("consumers::rdkafka_impl::pause::error: {:?}", error)
+            }
+            None => {}
+        }
+    }
+
+    /// Returns the partitions paused as soon as they were assigned since the last call
+    fn take_paused_on_assign(&self) -> Vec<TopicPartition> {
+        self.paused_on_assign
+            .lock()
+            .map(|mut paused_on_assign| std::mem::take(&mut *paused_on_assign))
+            .unwrap_or_default()
+    }
+
+    /// Returns the start offset of the consumer and the partitions that are waiting to be moved to it
+    fn take_pending_starts(&self) -> (StartOffset, Vec<TopicPartition>) {
+        match self.start.lock() {
+            Ok(mut start) => (
+                start.start_offset.clone(),
+                std::mem::take(&mut start.pending),
+            ),
+            Err(_) => (StartOffset::Committed, Vec::new()),
+        }
+    }
+
+    /// Commits the pending offsets of the revoked partitions before they are unassigned,
+    /// so that their next owner does not receive the events that were already processed again.
+    /// The pending offsets of lost partitions are dropped, they can no longer be committed.
+    fn commit_revoked(&self, partitions: &[TopicPartition], lost: bool) {
+        let mut offsets = TopicPartitionList::new();
+        if let Ok(mut pending_offsets) = self.pending_offsets.lock() {
+            for partition in partitions {
+                let key = (partition.topic.clone(), partition.partition);
+                if let Some(offset) = pending_offsets.remove(&key) {
+                    if let Err(error) = offsets.add_partition_offset(
+                        &partition.topic,
+                        partition.partition,
+                        Offset::Offset(offset),
+                    ) {
+                        tracing::error!("consumers::rdkafka_impl::revoke::error: {:?}", error);
+                    }
+                }
+            }
+        }
+        if lost || offsets.count() == 0 {
+            return;
+        }
+        if let Some(Err(error)) = self
+            .consumer()
+            .map(|consumer| consumer.commit(&offsets, CommitMode::Sync))
+        {
+            tracing::error!(
+                "consumers::rdkafka_impl::revoke::commit::error: {:?}",
+                error
+            );
+        }
+    }
+
+    /// Completes once partitions were assigned or revoked since the consumer loop last checked
+    async fn rebalanced(&self) {
+        self.rebalanced.notified().await
+    }
+
+    /// Returns true if partitions were assigned since the last call
+    fn take_assigned(&self) -> bool {
+        self.assigned.swap(false, Ordering::Relaxed)
+    }
+
+    /// Returns the partitions revoked since the last call
+    fn take_revoked(&self) -> Vec<TopicPartition> {
+        self.revoked
+            .lock()
+            .map(|mut revoked| std::mem::take(&mut *revoked))
+            .unwrap_or_default()
+    }
+}
+
+impl ClientContext for RebalanceContext {}
+
+/// The underlying client applies the assignments of the group, incrementally with the cooperative protocol,
+/// between `pre_rebalance` and `post_rebalance`
+impl ConsumerContext for RebalanceContext {
+    fn pre_rebalance(&self, rebalance: &Rebalance<'_>) {
+        match rebalance {
+            Rebalance::Assign(_) => {}
+            Rebalance::Revoke(tpl) => {
+                let partitions = topic_partitions(tpl);
+                tracing::info!("Revoked partitions {:?}", partitions);
+                let lost = self
+                    .consumer()
+                    .is_some_and(|consumer| consumer.assignment_lost());
+                // The listener is notified before the partitions are unassigned
+                match self.listener() {
+                    Some(listener) if lost => listener.on_lost(&partitions),
+                    Some(listener) => listener.on_revoked(&partitions),
+                    None => {}
+                }
+                self.commit_revoked(&partitions, lost);
+                if let Ok(mut start) = self.start.lock() {
+                    start
+                        .pending
+                        .retain(|partition| !partitions.contains(partition));
+                }
+                if let Ok(mut paused_on_assign) = self.paused_on_assign.lock() {
+                    paused_on_assign.retain(|partition| !partitions.contains(partition));
+                }
+                if let Ok(mut revoked) = self.revoked.lock() {
+                    revoked.extend(partitions);
+                }
+                self.rebalanced.notify_one();
+            }
+            Rebalance::Error(error) => {
+                tracing::error!("consumers::rdkafka_impl::rebalance::error: {:?}", error)
+            }
+        }
+    }
+
+    fn post_rebalance(&self, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Assign(tpl) = rebalance {
+            let partitions = topic_partitions(tpl);
+            tracing::info!("Assigned partitions {:?}", partitions);
+            self.hold_until_started(&partitions);
+            self.pause_if_paused(tpl);
+            self.assigned.store(true, Ordering::Relaxed);
+            self.rebalanced.notify_one();
+            if let Some(listener) = self.listener() {
+                listener.on_assigned(&partitions);
+            }
+        }
+    }
+}
+
+/// Moves the partitions assigned for the first time to the start offset of the consumer,
+/// then resumes the ones that are not paused on purpose.
+/// Returns an error if the partitions cannot be moved, rather than letting them resume from their committed offsets.
+fn move_to_start_offset(
+    consumer: &StreamConsumer<RebalanceContext>,
+    pauser: &Pauser,
+) -> KafkaResult<()> {
+    let (start_offset, partitions) = consumer.context().take_pending_starts();
+    if partitions.is_empty() {
+        return Ok(());
+    }
+    let mut offsets = TopicPartitionList::new();
+    for partition in &partitions {
+        let offset = match &start_offset {
+            StartOffset::Committed => continue,
+            StartOffset::Earliest => Offset::Beginning,
+            StartOffset::Latest => Offset::End,
+            StartOffset::Offsets(start_offsets) => match start_offsets.get(partition) {
+                Some(offset) => Offset::Offset(*offset),
+                None => continue,
+            },
+            // Replaced with the offset of the first event at or after the timestamp below
+            StartOffset::Timestamp(timestamp) => Offset::Offset(timestamp.timestamp_millis()),
+        };
+        offsets.add_partition_offset(&partition.topic, partition.partition, offset)?;
+    }
+    if let StartOffset::Timestamp(_) = start_offset {
+        offsets = consumer.offsets_for_times(offsets, OFFSETS_FOR_TIMES_TIMEOUT)?;
+    }
+    for element in offsets.elements() {
+        element.error()?;
+        tracing::info!(
+            "Starting {}[{}] at {:?}",
+            element.topic(),
+            element.partition(),
+            element.offset()
+        );
+    }
+    if offsets.count() > 0 {
+        for element in consumer.seek_partitions(offsets, SEEK_TIMEOUT)?.elements() {
+            element.error()?;
+        }
+    }
+    let mut resuming = TopicPartitionList::new();
+    for partition in partitions {
+        if !pauser.is_paused(&partition.topic, partition.partition) {
+            resuming.add_partition(&partition.topic, partition.partition);
+        }
+    }
+    consumer.resume(&resuming)?;
+    Ok(())
+}
+
+fn topic_partitions(partitions: &TopicPartitionList) -> Vec<TopicPartition> {
+    partitions
+        .elements()
+        .iter()
+        .map(|element| TopicPartition {
+            topic: element.topic().to_string(),
+            partition: element.partition(),
+        })
+        .collect()
+}
 
 #[async_trait]
 impl<Dispatcher: EventDispatcher, InnerProducer: KafkaProducerInterface>
-    KafkaConsumerInterface<Dispatcher, InnerProducer> for StreamConsumer
+    KafkaConsumerInterface<Dispatcher, InnerProducer> for Arc<StreamConsumer<RebalanceContext>>
 {
-    fn new(consumer_group_id: String, bootstrap_servers: String) -> Self {
+    fn new(
+        consumer_group_id: String,
+        bootstrap_servers: String,
+        config: ConsumerConfig,
+    ) -> KafkaResult<Self> {
         tracing::info!("Creating consumer with group ID {}", consumer_group_id);
-        // TODO: configure all properties
-        ClientConfig::new()
-            .set("group.id", consumer_group_id)
-            .set("bootstrap.servers", bootstrap_servers)
-            .set("enable.partition.eof", "false")
-            .set("session.timeout.ms", "6000")
-            .set_log_level(RDKafkaLogLevel::Debug)
-            .create::<StreamConsumer>()
-            .expect("Consumer creation failed")
+        let consumer: StreamConsumer<RebalanceContext> =
+            consumer_client_config(consumer_group_id, bootstrap_servers, &config)?
+                .create_with_context(RebalanceContext::default())
+                .map_err(|e| {
+                    tracing::error!("Consumer creation failed: {:?}", e);
+                    KafkaError::from(e)
+                })?;
+        let consumer = Arc::new(consumer);
+        // The context pauses and inspects the partitions of its consumer while it is rebalanced
+        let _ = consumer.context().consumer.set(Arc::downgrade(&consumer));
+        Ok(consumer)
     }
 
     async fn start<'a>(
         &'a self,
         dispatcher: &'a Dispatcher,
         dlq_producer: &'a KafkaProducer<InnerProducer>,
-        topic: KafkaTopic,
+        subscription: Subscription,
         dlq_topic: KafkaTopic,
-    ) {
-        self.subscribe(&[topic.name.as_str()])
-            .map(|()| tracing::info!("Subscribed to {}", topic.name.as_str()))
-            .expect("Can't subscribe to specified topics");
+        options: &'a ConsumerOptions,
+        handle: ConsumerHandle,
+    ) -> KafkaResult<()> {
+        let shutdown = handle.shutdown_token();
+        self.context()
+            .set_listener(options.rebalance_listener.clone());
+        self.context()
+            .set_start_offset(options.start_offset.clone(), options.retry_topics.clone());
+        let subscribed_names = subscription.subscribed_names(options.retry_topics.as_ref());
+        let topic_names = subscribed_names
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        self.subscribe(&topic_names)
+            .map(|()| tracing::info!("Subscribed to {:?}", topic_names))
+            .map_err(|e| {
+                tracing::error!("Can't subscribe to specified topics: {:?}", e);
+                KafkaError::from(e)
+            })?;
+        let (acknowledgements, mut acknowledged) = mpsc::unbounded_channel();
+        let dispatcher = TimeoutDispatcher::new(dispatcher, options.handler_timeout);
+        let dispatcher = MiddlewareDispatcher::new(&dispatcher, &options.middlewares);
+        let processor = EventProcessor {
+            dispatcher: &dispatcher,
+            dlq_producer,
+            subscription: &subscription,
+            dlq_topic: &dlq_topic,
+            options,
+            shutdown: &shutdown,
+            acknowledgements: (options.commit_strategy == CommitStrategy::Manual)
+                .then_some(acknowledgements),
+            fatal_error: Mutex::new(None),
+        };
+        let mut committer = Committer::new(self, &options.commit_strategy);
+        let max_in_flight = options.concurrency.max_in_flight();
+        let mut in_flight = 0;
+        // Events received while the consumer is at capacity, it keeps polling to serve rebalances
+        let mut buffered: VecDeque<TrackedEvent> = VecDeque::new();
+        let mut lanes = Lanes::default();
+        let mut offsets = OffsetTracker::default();
+        let mut processing = FuturesUnordered::new();
+        let mut batch: Vec<TrackedEvent> = Vec::new();
+        let mut batch_deadline = None;
+        let mut delayed_partitions: Vec<DelayedPartition> = Vec::new();
+        let mut pauser = Pauser::default();
+        let backpressure = options.backpressure.as_ref();
+        // The error that stopped the consumer, if any
+        let mut stop_error = None;
         loop {
-            match self.recv().await {
-                Ok(event) => {
-                    tracing::debug!("event: {:?}", event);
-                    let result = &dispatcher.dispatch_event(&event).await;
-                    match result {
-                        Ok(_) => {}
-                        Err(error) => {
-                            tracing::error!("consumers::rdkafka_impl::error: {:?}", error);
-                            let unhandled_event = event.detach().set_topic(dlq_topic.name.clone());
-                            match dlq_producer.send(unhandled_event).await {
-                                Ok(_) => {
-                                    tracing::info!("Sent event to DLQ");
-                                }
-                                Err(error) => {
-                                    tracing::error!(
-                                        "consumers::rdkafka_impl::dlq::error: {:?}",
-                                        error
-                                    );
+            // Rebalances happen while polling, the offsets of revoked partitions belong to
+            // the next owner of the partitions and must no longer be committed. Their events
+            // that are still being dispatched complete in a previous epoch and are ignored.
+            for revoked in self.context().take_revoked() {
+                offsets.forget(&revoked.topic, revoked.partition);
+                pauser.forget(&revoked);
+                buffered.retain(|tracked| !is_from(&tracked.event, &revoked));
+                let batched = batch.len();
+                batch.retain(|tracked| !is_from(&tracked.event, &revoked));
+                in_flight -= batched - batch.len();
+                if batch.is_empty() {
+                    batch_deadline = None;
+                }
+                in_flight -= lanes.forget(&revoked.topic, revoked.partition);
+                delayed_partitions.retain(|delayed_partition| {
+                    delayed_partition.topic != revoked.topic
+                        || delayed_partition.partition != revoked.partition
+                });
+            }
+            if self.context().take_assigned() {
+                pauser.adopt(self.context().take_paused_on_assign());
+                pauser.reconcile(self, &handle, &delayed_partitions);
+                if let Err(error) = move_to_start_offset(self, &pauser) {
+                    tracing::error!(
+                        "consumers::rdkafka_impl::move_to_start_offset::error: {:?}",
+                        error
+                    );
+                    stop_error = Some(error);
+                    break;
+                }
+            }
+            while !is_at_capacity(options, &batch, in_flight) {
+                let Some(tracked) = buffered.pop_front() else {
+                    break;
+                };
+                in_flight += 1;
+                if let Some(batching) = &options.batching {
+                    if batch.is_empty() {
+                        batch_deadline = Some(Instant::now() + batching.max_wait);
+                    }
+                    batch.push(tracked);
+                } else if let Some(tracked) =
+                    lanes.push(lane(&tracked, &options.concurrency), tracked)
+                {
+                    processing.push(processor.process(vec![tracked]));
+                }
+            }
+            if let Some(batching) = &options.batching {
+                let is_due = batch.len() >= batching.max_size
+                    || batch_deadline.is_some_and(|deadline| deadline <= Instant::now());
+                if is_due && processing.is_empty() {
+                    batch_deadline = None;
+                    processing.push(processor.process(std::mem::take(&mut batch)));
+                }
+            }
+            // Partitions are paused while too many events are buffered, instead of no longer polling
+            let capacity = options
+                .batching
+                .as_ref()
+                .map_or(max_in_flight, |batching| batching.max_size);
+            if pauser.update_buffer(buffered.len(), capacity) {
+                pauser.reconcile(self, &handle, &delayed_partitions);
+            }
+            let next_resume = delayed_partitions
+                .iter()
+                .map(|delayed_partition| delayed_partition.resume_at)
+                .min();
+            // Only the polling is raced against the shutdown signal, events that are
+            // being dispatched are always allowed to finish.
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = sleep_until(next_resume) => {
+                    resume_due_partitions(self, &mut delayed_partitions, &pauser);
+                }
+                _ = handle.pauses_changed() => pauser.reconcile(self, &handle, &delayed_partitions),
+                _ = pressure_signal_changed(backpressure) => {
+                    if pauser.update_pressure(backpressure, in_flight + buffered.len()) {
+                        pauser.reconcile(self, &handle, &delayed_partitions);
+                    }
+                }
+                _ = sleep_until(batch_deadline), if processing.is_empty() => {}
+                _ = sleep_until(committer.deadline()) => committer.flush(),
+                Some(acknowledged_offsets) = acknowledged.recv() => {
+                    for TrackedOffset { topic, partition, offset, epoch } in acknowledged_offsets {
+                        let committable = offsets.complete(&topic, partition, offset, epoch);
+                        committer.processed(&topic, partition, committable);
+                    }
+                }
+                Some(processed_events) = processing.next(), if !processing.is_empty() => {
+                    let mut interrupted = false;
+                    for ProcessedEvent { tracked, committable } in processed_events {
+                        let TrackedEvent { event, epoch } = &tracked;
+                        in_flight -= 1;
+                        if !committable {
+                            // The event was interrupted by the shutdown, it is left uncommitted
+                            // so that it is consumed again after a restart.
+                            interrupted = true;
+                            continue;
+                        }
+                        // With manual commits, offsets are completed once they are acknowledged
+                        if processor.acknowledgements.is_none() {
+                            let (topic, partition) = (event.topic(), event.partition());
+                            let committable =
+                                offsets.complete(topic, partition, event.offset(), *epoch);
+                            committer.processed(topic, partition, committable);
+                        }
+                        let lane = lane(&tracked, &options.concurrency);
+                        if let Some(next_event) = lanes.complete(&lane) {
+                            processing.push(processor.process(vec![next_event]));
+                        }
+                    }
+                    if interrupted {
+                        break;
+                    }
+                    if pauser.update_pressure(backpressure, in_flight + buffered.len()) {
+                        pauser.reconcile(self, &handle, &delayed_partitions);
+                    }
+                }
+                // Rebalances are handled at the top of the loop, even if no event is received
+                _ = self.context().rebalanced() => {}
+                received = self.recv() => {
+                    match received {
+                        Ok(event) => {
+                            tracing::debug!("event: {:?}", event);
+                            if let Some(delay) = retry_delay(&event, options) {
+                                match delay_partition(self, &event, delay) {
+                                    Ok(delayed_partition) => {
+                                        delayed_partitions.push(delayed_partition);
+                                        continue;
+                                    }
+                                    // The event can neither wait for its retry nor be skipped
+                                    // without being lost, the consumer stops without committing it.
+                                    Err(error) => {
+                                        tracing::error!(
+                                            "consumers::rdkafka_impl::delay_partition::error: {:?}",
+                                            error
+                                        );
+                                        stop_error = Some(error);
+                                        break;
+                                    }
                                 }
                             }
+                            let event = event.detach();
+                            let epoch =
+                                offsets.track(event.topic(), event.partition(), event.offset());
+                            // The event is dispatched at the top of the loop, once there is room for it
+                            buffered.push_back(TrackedEvent { event, epoch });
+                            if pauser.update_pressure(backpressure, in_flight + buffered.len()) {
+                                pauser.reconcile(self, &handle, &delayed_partitions);
+                            }
                         }
-                    }
-                    match self.commit_message(&event, CommitMode::Async) {
-                        Ok(_) => {}
                         Err(error) => {
-                            tracing::error!(
-                                "consumers::rdkafka_impl::commit_message::error: {:?}",
-                                error
-                            );
+                            tracing::error!("Kafka error: {}", error);
                         }
                     }
                 }
+            }
+        }
+        tracing::info!("Shutting down consumer of {}", subscription);
+        // Events that are being dispatched are allowed to finish, queued events are left uncommitted
+        while let Some(processed_events) = processing.next().await {
+            for ProcessedEvent {
+                tracked,
+                committable,
+            } in processed_events
+            {
+                if committable && processor.acknowledgements.is_none() {
+                    let TrackedEvent { event, epoch } = tracked;
+                    offsets.complete(event.topic(), event.partition(), event.offset(), epoch);
+                }
+            }
+        }
+        acknowledged.close();
+        while let Some(acknowledged_offsets) = acknowledged.recv().await {
+            for TrackedOffset {
+                topic,
+                partition,
+                offset,
+                epoch,
+            } in acknowledged_offsets
+            {
+                offsets.complete(&topic, partition, offset, epoch);
+            }
+        }
+        let mut processed_offsets = TopicPartitionList::new();
+        for (topic, partition, offset) in offsets.committable_offsets() {
+            if let Err(error) =
+                processed_offsets.add_partition_offset(topic, partition, Offset::Offset(offset))
+            {
+                tracing::error!(
+                    "consumers::rdkafka_impl::processed_offsets::error: {:?}",
+                    error
+                );
+            }
+        }
+        if processed_offsets.count() > 0 {
+            match self.commit(&processed_offsets, CommitMode::Sync) {
+                Ok(_) => tracing::info!("Committed final offsets"),
+                Err(error) => {
+                    tracing::error!(
+                        "consumers::rdkafka_impl::shutdown::commit::error: {:?}",
+                        error
+                    );
+                }
+            }
+        }
+        // Unsubscribing revokes the assignment, the group is left for good
+        // once the consumer is dropped and closed.
+        self.unsubscribe();
+        let fatal_error = processor
+            .fatal_error
+            .lock()
+            .ok()
+            .and_then(|mut fatal_error| fatal_error.take());
+        match (stop_error, fatal_error) {
+            (Some(error), _) => Err(error),
+            (None, Some(reason)) => Err(KafkaError::Fatal(reason)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// An event along with the epoch of its partition it was received in, see `OffsetTracker::track`
+struct TrackedEvent {
+    event: OwnedMessage,
+    epoch: u64,
+}
+
+/// Returns true if the event was received from the partition
+fn is_from(event: &OwnedMessage, partition: &TopicPartition) -> bool {
+    event.topic() == partition.topic && event.partition() == partition.partition
+}
+
+/// Returns true if the consumer cannot take another event until it is done with some of the current ones
+fn is_at_capacity(options: &ConsumerOptions, batch: &[TrackedEvent], in_flight: usize) -> bool {
+    match &options.batching {
+        Some(batching) => batch.len() >= batching.max_size,
+        None => in_flight >= options.concurrency.max_in_flight(),
+    }
+}
+
+fn lane(tracked: &TrackedEvent, concurrency: &Concurrency) -> Lane {
+    let event = &tracked.event;
+    Lane {
+        topic: event.topic().to_string(),
+        partition: event.partition(),
+        key: match concurrency {
+            Concurrency::Key { .. } => event.key().map(<[u8]>::to_vec),
+            Concurrency::Sequential | Concurrency::Partition { .. } => None,
+        },
+        epoch: tracked.epoch,
+    }
+}
+
+fn commit_offset(
+    consumer: &StreamConsumer<RebalanceContext>,
+    topic: &str,
+    partition: i32,
+    offset: i64,
+    mode: CommitMode,
+) {
+    let mut offsets = TopicPartitionList::new();
+    let result = offsets
+        .add_partition_offset(topic, partition, Offset::Offset(offset))
+        .and_then(|()| consumer.commit(&offsets, mode));
+    if let Err(error) = result {
+        tracing::error!("consumers::rdkafka_impl::commit::error: {:?}", error);
+    }
+}
+
+/// Commits the offsets of the processed events according to the commit strategy of the consumer
+struct Committer<'a> {
+    consumer: &'a StreamConsumer<RebalanceContext>,
+    strategy: &'a CommitStrategy,
+    pending_events: usize,
+    pending_since: Option<Instant>,
+}
+
+impl<'a> Committer<'a> {
+    fn new(consumer: &'a StreamConsumer<RebalanceContext>, strategy: &'a CommitStrategy) -> Self {
+        Self {
+            consumer,
+            strategy,
+            pending_events: 0,
+            pending_since: None,
+        }
+    }
+
+    /// Called once for every processed event, with the offset that can now be committed for its partition, if any
+    fn processed(&mut self, topic: &str, partition: i32, committable: Option<i64>) {
+        match self.strategy {
+            CommitStrategy::PerEventAsync | CommitStrategy::Manual => {
+                if let Some(offset) = committable {
+                    commit_offset(self.consumer, topic, partition, offset, CommitMode::Async);
+                }
+            }
+            CommitStrategy::PerEventSync => {
+                if let Some(offset) = committable {
+                    commit_offset(self.consumer, topic, partition, offset, CommitMode::Sync);
+                }
+            }
+            CommitStrategy::Auto => {
+                if let Some(offset) = committable {
+                    store_offset(self.consumer, topic, partition, offset);
+                }
+            }
+            CommitStrategy::Batched { max_events, .. } => {
+                if let Some(offset) = committable {
+                    // Kept by the context, that commits them if the partition is revoked
+                    if let Ok(mut pending_offsets) = self.consumer.context().pending_offsets.lock()
+                    {
+                        pending_offsets.insert((topic.to_string(), partition), offset);
+                    }
+                }
+                self.pending_events += 1;
+                self.pending_since.get_or_insert_with(Instant::now);
+                if self.pending_events >= *max_events {
+                    self.flush();
+                }
+            }
+        }
+    }
+
+    /// When the pending offsets are due to be committed
+    fn deadline(&self) -> Option<Instant> {
+        match self.strategy {
+            CommitStrategy::Batched { interval, .. } => self
+                .pending_since
+                .map(|pending_since| pending_since + *interval),
+            _ => None,
+        }
+    }
+
+    /// Commits the pending offsets
+    fn flush(&mut self) {
+        self.pending_events = 0;
+        self.pending_since = None;
+        let pending_offsets = match self.consumer.context().pending_offsets.lock() {
+            Ok(mut pending_offsets) => std::mem::take(&mut *pending_offsets),
+            Err(_) => return,
+        };
+        if pending_offsets.is_empty() {
+            return;
+        }
+        let mut offsets = TopicPartitionList::new();
+        for ((topic, partition), offset) in pending_offsets {
+            if let Err(error) =
+                offsets.add_partition_offset(&topic, partition, Offset::Offset(offset))
+            {
+                tracing::error!("consumers::rdkafka_impl::flush::error: {:?}", error);
+            }
+        }
+        if let Err(error) = self.consumer.commit(&offsets, CommitMode::Async) {
+            tracing::error!("consumers::rdkafka_impl::commit::error: {:?}", error);
+        }
+    }
+}
+
+fn store_offset(
+    consumer: &StreamConsumer<RebalanceContext>,
+    topic: &str,
+    partition: i32,
+    offset: i64,
+) {
+    let mut offsets = TopicPartitionList::new();
+    let result = offsets
+        .add_partition_offset(topic, partition, Offset::Offset(offset))
+        .and_then(|()| consumer.store_offsets(&offsets));
+    if let Err(error) = result {
+        tracing::error!("consumers::rdkafka_impl::store_offset::error: {:?}", error);
+    }
+}
+
+/// A partition of a retry topic that is paused until its next event is due
+struct DelayedPartition {
+    topic: String,
+    partition: i32,
+    resume_at: Instant,
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Returns how long to wait before the event can be dispatched,
+/// if it was read from a retry topic before its retry delay has passed.
+fn retry_delay(event: &BorrowedMessage<'_>, options: &ConsumerOptions) -> Option<Duration> {
+    options
+        .retry_topics
+        .as_ref()?
+        .original_topic(event.topic())?;
+    let headers = kafka_message::KafkaMessage::headers(event).ok()?;
+    let remaining_ms = retry_after(&headers)? - chrono::Utc::now().timestamp_millis();
+    (remaining_ms > 0).then(|| Duration::from_millis(remaining_ms as u64))
+}
+
+/// Pauses the partition of the event and rewinds it so that the event is received again once the partition is resumed
+fn delay_partition(
+    consumer: &StreamConsumer<RebalanceContext>,
+    event: &BorrowedMessage<'_>,
+    delay: Duration,
+) -> KafkaResult<DelayedPartition> {
+    let mut partitions = TopicPartitionList::new();
+    partitions.add_partition(event.topic(), event.partition());
+    consumer.pause(&partitions)?;
+    consumer.seek(
+        event.topic(),
+        event.partition(),
+        Offset::Offset(event.offset()),
+        Duration::from_secs(5),
+    )?;
+    tracing::debug!(
+        "Pausing {}[{}] for {:?} until the retry is due",
+        event.topic(),
+        event.partition(),
+        delay
+    );
+    Ok(DelayedPartition {
+        topic: event.topic().to_string(),
+        partition: event.partition(),
+        resume_at: Instant::now() + delay,
+    })
+}
+
+fn resume_due_partitions(
+    consumer: &StreamConsumer<RebalanceContext>,
+    delayed_partitions: &mut Vec<DelayedPartition>,
+    pauser: &Pauser,
+) {
+    let now = Instant::now();
+    let mut partitions = TopicPartitionList::new();
+    delayed_partitions.retain(|delayed_partition| {
+        let is_due = delayed_partition.resume_at <= now;
+        // Partitions that are paused on purpose stay paused until they are resumed
+        if is_due && !pauser.is_paused(&delayed_partition.topic, delayed_partition.partition) {
+            partitions.add_partition(&delayed_partition.topic, delayed_partition.partition);
+        }
+        !is_due
+    });
+    if let Err(error) = consumer.resume(&partitions) {
+        tracing::error!("consumers::rdkafka_impl::resume::error: {:?}", error);
+    }
+}
+
+fn acknowledged_offsets(events: &[OwnedMessage], epochs: &[u64]) -> AcknowledgedOffsets {
+    events
+        .iter()
+        .zip(epochs)
+        .map(|(event, epoch)| TrackedOffset {
+            topic: event.topic().to_string(),
+            partition: event.partition(),
+            offset: event.offset(),
+            epoch: *epoch,
+        })
+        .collect()
+}
+
+async fn pressure_signal_changed(backpressure: Option<&Backpressure>) {
+    match backpressure.and_then(|backpressure| backpressure.signal.as_ref()) {
+        Some(signal) => signal.changed().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// How many events the consumer buffers at least once it is at capacity before it pauses all its partitions,
+/// so that a consumer processing one event at a time does not pause and resume them for every event
+const MIN_BUFFERED_EVENTS: usize = 100;
+
+/// Pauses the partitions requested through the consumer handle,
+/// or all of them while the consumer is overloaded or has buffered too many events
+#[derive(Default)]
+struct Pauser {
+    paused: HashSet<TopicPartition>,
+    in_flight_exceeded: bool,
+    signal_exceeded: bool,
+    buffer_exceeded: bool,
+}
+
+impl Pauser {
+    /// Updates the backpressure of the consumer and returns true if it became, or stopped being, overloaded
+    fn update_pressure(&mut self, backpressure: Option<&Backpressure>, in_flight: usize) -> bool {
+        let Some(backpressure) = backpressure else {
+            return false;
+        };
+        let was_overloaded = self.is_overloaded();
+        if let Some(max_in_flight) = &backpressure.max_in_flight {
+            self.in_flight_exceeded =
+                max_in_flight.is_exceeded(in_flight as u64, self.in_flight_exceeded);
+        }
+        if let Some(signal) = &backpressure.signal {
+            self.signal_exceeded = signal
+                .thresholds()
+                .is_exceeded(signal.level(), self.signal_exceeded);
+        }
+        was_overloaded != self.is_overloaded()
+    }
+
+    /// Updates the number of events buffered while the consumer is at capacity and returns true if
+    /// the buffer became, or stopped being, full. It is full at `capacity` events and drained at half of it.
+    fn update_buffer(&mut self, buffered: usize, capacity: usize) -> bool {
+        let was_overloaded = self.is_overloaded();
+        let max_buffered = capacity.max(MIN_BUFFERED_EVENTS) as u64;
+        self.buffer_exceeded = Thresholds::new(max_buffered, max_buffered / 2)
+            .is_exceeded(buffered as u64, self.buffer_exceeded);
+        was_overloaded != self.is_overloaded()
+    }
+
+    fn is_overloaded(&self) -> bool {
+        self.in_flight_exceeded || self.signal_exceeded || self.buffer_exceeded
+    }
+
+    fn is_paused(&self, topic: &str, partition: i32) -> bool {
+        self.paused.contains(&TopicPartition {
+            topic: topic.to_string(),
+            partition,
+        })
+    }
+
+    /// Takes over the partitions that the context paused as soon as they were assigned,
+    /// they are resumed by the next reconciliation if they should no longer be paused
+    fn adopt(&mut self, partitions: Vec<TopicPartition>) {
+        self.paused.extend(partitions);
+    }
+
+    /// Forgets a partition that was revoked, its next assignment starts unpaused
+    fn forget(&mut self, partition: &TopicPartition) {
+        self.paused.remove(partition);
+    }
+
+    /// Pauses and resumes partitions so that exactly the ones that should be paused are
+    fn reconcile(
+        &mut self,
+        consumer: &StreamConsumer<RebalanceContext>,
+        handle: &ConsumerHandle,
+        delayed_partitions: &[DelayedPartition],
+    ) {
+        let requested = handle.requested_pauses();
+        let pause_all = requested.all || self.is_overloaded();
+        consumer
+            .context()
+            .pause_assigned
+            .store(pause_all, Ordering::Relaxed);
+        let assignment: HashSet<TopicPartition> = match consumer.assignment() {
+            Ok(assignment) => topic_partitions(&assignment).into_iter().collect(),
+            Err(error) => {
+                tracing::error!("consumers::rdkafka_impl::assignment::error: {:?}", error);
+                return;
+            }
+        };
+        // Requested partitions that are not assigned yet are paused once they are
+        let to_pause: HashSet<TopicPartition> = if pause_all {
+            assignment
+        } else {
+            requested
+                .partitions
+                .intersection(&assignment)
+                .cloned()
+                .collect()
+        };
+        let mut pausing = TopicPartitionList::new();
+        for partition in to_pause.difference(&self.paused) {
+            pausing.add_partition(&partition.topic, partition.partition);
+        }
+        let mut resuming = TopicPartitionList::new();
+        for partition in self.paused.difference(&to_pause) {
+            // Delayed partitions are resumed once their retry is due
+            let is_delayed = delayed_partitions.iter().any(|delayed_partition| {
+                delayed_partition.topic == partition.topic
+                    && delayed_partition.partition == partition.partition
+            });
+            if !is_delayed {
+                resuming.add_partition(&partition.topic, partition.partition);
+            }
+        }
+        if pausing.count() > 0 {
+            match consumer.pause(&pausing) {
+                Ok(()) => tracing::info!("Paused partitions {:?}", topic_partitions(&pausing)),
+                Err(error) => tracing::error!("consumers::rdkafka_impl::pause::error: {:?}", error),
+            }
+        }
+        if resuming.count() > 0 {
+            match consumer.resume(&resuming) {
+                Ok(()) => tracing::info!("Resumed partitions {:?}", topic_partitions(&resuming)),
                 Err(error) => {
-                    tracing::error!("Kafka error: {}", error);
+                    tracing::error!("consumers::rdkafka_impl::resume::error: {:?}", error)
                 }
             }
         }
+        self.paused = to_pause;
+    }
+}
+
+/// An event the consumer is done with
+struct ProcessedEvent {
+    tracked: TrackedEvent,
+    /// False if the event was neither handled nor forwarded, and its offset must not be committed
+    committable: bool,
+}
+
+/// Everything the consumer needs to dispatch an event and forward it if it fails
+struct EventProcessor<'a, Dispatcher: EventDispatcher, InnerProducer: KafkaProducerInterface> {
+    dispatcher: &'a Dispatcher,
+    dlq_producer: &'a KafkaProducer<InnerProducer>,
+    subscription: &'a Subscription,
+    dlq_topic: &'a KafkaTopic,
+    options: &'a ConsumerOptions,
+    shutdown: &'a CancellationToken,
+    /// Where the acknowledgements of the handlers are sent to, with `CommitStrategy::Manual`
+    acknowledgements: Option<UnboundedSender<AcknowledgedOffsets>>,
+    /// The reason of the first `HandlerOutcome::Fatal`, which stops the consumer
+    fatal_error: Mutex<Option<String>>,
+}
+
+impl<'a, Dispatcher: EventDispatcher, InnerProducer: KafkaProducerInterface>
+    EventProcessor<'a, Dispatcher, InnerProducer>
+{
+    /// Dispatches the events, as a batch if there are more than one, and forwards the ones that fail
+    async fn process(&self, tracked_events: Vec<TrackedEvent>) -> Vec<ProcessedEvent> {
+        let (events, epochs): (Vec<_>, Vec<_>) = tracked_events
+            .into_iter()
+            .map(|tracked| (tracked.event, tracked.epoch))
+            .unzip();
+        let results = match &self.acknowledgements {
+            Some(acknowledgements) => {
+                Acknowledgement::new(
+                    acknowledged_offsets(&events, &epochs),
+                    acknowledgements.clone(),
+                )
+                .scope(self.dispatch(&events))
+                .await
+            }
+            None => self.dispatch(&events).await,
+        };
+        let mut processed_events = Vec::with_capacity(events.len());
+        for ((event, epoch), result) in events.into_iter().zip(epochs).zip(results) {
+            let failed = result.is_err();
+            let committable = self.settle(&event, result).await;
+            if let Some(acknowledgements) = self.acknowledgements.as_ref().filter(|_| failed) {
+                if committable {
+                    Acknowledgement::new(
+                        acknowledged_offsets(std::slice::from_ref(&event), &[epoch]),
+                        acknowledgements.clone(),
+                    )
+                    .ack();
+                }
+            }
+            processed_events.push(ProcessedEvent {
+                tracked: TrackedEvent { event, epoch },
+                committable,
+            });
+        }
+        processed_events
+    }
+
+    /// Dispatches the events and retries the ones that fail according to the retry policy
+    async fn dispatch(&self, events: &[OwnedMessage]) -> Vec<Result<(), FailedDispatch>> {
+        let retry_policy = &self.options.retry_policy;
+        if let [event] = events {
+            return vec![
+                dispatch_with_retry(self.dispatcher, event, retry_policy, self.shutdown).await,
+            ];
+        }
+        let results = self.dispatcher.dispatch_batch(events).await;
+        let mut dispatched = Vec::with_capacity(events.len());
+        for (event, result) in events.iter().zip(results) {
+            dispatched.push(match result {
+                Ok(()) => Ok(()),
+                Err(error) => {
+                    retry_failed_dispatch(
+                        self.dispatcher,
+                        event,
+                        retry_policy,
+                        self.shutdown,
+                        error,
+                    )
+                    .await
+                }
+            });
+        }
+        dispatched
+    }
+
+    /// Forwards the event if it could not be dispatched.
+    /// Returns false if the offset of the event must not be committed.
+    async fn settle(&self, event: &OwnedMessage, result: Result<(), FailedDispatch>) -> bool {
+        match result {
+            Ok(_) => true,
+            Err(FailedDispatch {
+                error: KafkaError::Fatal(reason),
+                ..
+            }) => {
+                tracing::error!(
+                    "Stopping the consumer, the event from {}[{}] at offset {} failed with a fatal error: {}",
+                    event.topic(),
+                    event.partition(),
+                    event.offset(),
+                    reason
+                );
+                if let Ok(mut fatal_error) = self.fatal_error.lock() {
+                    fatal_error.get_or_insert(reason);
+                }
+                self.shutdown.cancel();
+                false
+            }
+            // The retries were interrupted by the shutdown
+            Err(_) if self.shutdown.is_cancelled() => false,
+            Err(failed_dispatch) => {
+                tracing::error!(
+                    "consumers::rdkafka_impl::error: {:?}",
+                    failed_dispatch.error
+                );
+                self.forward_failed_event_with_mode(event, &failed_dispatch)
+                    .await
+            }
+        }
+    }
+
+    /// Forwards a failed event according to the DLQ failure mode of the consumer.
+    /// Returns false if the event could not be forwarded and its offset must not be committed.
+    async fn forward_failed_event_with_mode(
+        &self,
+        event: &OwnedMessage,
+        failed_dispatch: &FailedDispatch,
+    ) -> bool {
+        let mut attempt = 1;
+        loop {
+            let error = match self.forward_failed_event(event, failed_dispatch).await {
+                Ok(()) => return true,
+                Err(error) => error,
+            };
+            match &self.options.dlq_failure_mode {
+                DlqFailureMode::LogAndCommit => {
+                    tracing::error!(
+                        "consumers::rdkafka_impl::forward_failed_event::error: {:?}",
+                        error
+                    );
+                    return true;
+                }
+                // Forwarding the event again would fail the same way, e.g. if it is too large
+                // for the topic. It is never committed without being forwarded: the consumer stops.
+                DlqFailureMode::Block { .. } if !error.is_transient() => {
+                    let reason = format!(
+                        "The failed event from {}[{}] at offset {} cannot be forwarded: {}",
+                        event.topic(),
+                        event.partition(),
+                        event.offset(),
+                        error
+                    );
+                    tracing::error!("Stopping the consumer: {}", reason);
+                    if let Ok(mut fatal_error) = self.fatal_error.lock() {
+                        fatal_error.get_or_insert(reason);
+                    }
+                    self.shutdown.cancel();
+                    return false;
+                }
+                DlqFailureMode::Block {
+                    initial_backoff,
+                    max_backoff,
+                } => {
+                    let backoff =
+                        RetryPolicy::exponential(u32::MAX, *initial_backoff, *max_backoff)
+                            .backoff(attempt);
+                    tracing::error!(
+                        "Attempt {} to forward failed event from {}[{}] at offset {} failed, retrying in {:?}: {:?}",
+                        attempt,
+                        event.topic(),
+                        event.partition(),
+                        event.offset(),
+                        backoff,
+                        error
+                    );
+                    tokio::select! {
+                        _ = self.shutdown.cancelled() => return false,
+                        _ = tokio::time::sleep(backoff) => {}
+                    }
+                    attempt = attempt.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    /// Sends an event that could not be handled to the next retry topic,
+    /// or to the dead letter queue if there are no retry topics left or the error is not retryable.
+    async fn forward_failed_event(
+        &self,
+        event: &OwnedMessage,
+        failed_dispatch: &FailedDispatch,
+    ) -> KafkaResult<()> {
+        let options = self.options;
+        let mut headers = kafka_message::KafkaMessage::headers(event).unwrap_or_default();
+        let attempt = retry_attempt(&headers);
+        let original_topic = headers
+            .get(RETRY_ORIGINAL_TOPIC_HEADER)
+            .cloned()
+            .unwrap_or_else(|| event.topic().to_string());
+        let next_retry_topic = options
+            .retry_topics
+            .as_ref()
+            .filter(|_| options.retry_policy.is_retryable(&failed_dispatch.error))
+            .and_then(|retry_topics| {
+                retry_topics.next(&self.subscription.topic(&original_topic), attempt)
+            });
+        let Some((retry_topic, delay)) = next_retry_topic else {
+            let dlq_topic = options
+                .dlq_topics
+                .get(&original_topic)
+                .unwrap_or(self.dlq_topic);
+            let metadata = DeadLetterMetadata::new(
+                original_topic,
+                event.partition(),
+                event.offset(),
+                event.timestamp().to_millis(),
+                options.consumer_group_id.clone(),
+                &failed_dispatch.error,
+                failed_dispatch.attempts,
+            );
+            headers.extend(metadata.to_headers());
+            self.dlq_producer
+                .send(ForwardedEvent::new(event, dlq_topic, headers))
+                .await?;
+            tracing::info!("Sent event to DLQ {}", dlq_topic.name);
+            return Ok(());
+        };
+        headers.insert(RETRY_ATTEMPT_HEADER.to_string(), (attempt + 1).to_string());
+        headers.insert(
+            RETRY_AFTER_HEADER.to_string(),
+            (chrono::Utc::now().timestamp_millis() + delay.as_millis() as i64).to_string(),
+        );
+        headers.insert(RETRY_ORIGINAL_TOPIC_HEADER.to_string(), original_topic);
+        self.dlq_producer
+            .send(ForwardedEvent::new(event, &retry_topic, headers))
+            .await?;
+        tracing::info!("Sent event to retry topic {}", retry_topic.name);
+        Ok(())
+    }
+}
+
+/// A failed event on its way to a retry topic or a dead letter queue, with its key and payload as they were consumed,
+/// so that events without a key or a payload, or that are not valid UTF-8, can be forwarded too
+struct ForwardedEvent {
+    topic: KafkaTopic,
+    key: Option<Vec<u8>>,
+    payload: Option<Vec<u8>>,
+    headers: kafka_message::Headers,
+}
+
+impl ForwardedEvent {
+    fn new(event: &OwnedMessage, topic: &KafkaTopic, headers: kafka_message::Headers) -> Self {
+        Self {
+            topic: topic.clone(),
+            key: event.key().map(<[u8]>::to_vec),
+            payload: event.payload().map(<[u8]>::to_vec),
+            headers,
+        }
+    }
+}
+
+impl kafka_message::KafkaMessage<Option<Vec<u8>>, Option<Vec<u8>>> for ForwardedEvent {
+    fn topic(&self) -> KafkaResult<KafkaTopic> {
+        Ok(self.topic.clone())
+    }
+
+    fn payload(&self) -> KafkaResult<Option<Vec<u8>>> {
+        Ok(self.payload.clone())
+    }
+
+    fn key(&self) -> KafkaResult<Option<Vec<u8>>> {
+        Ok(self.key.clone())
+    }
+
+    fn headers(&self) -> KafkaResult<kafka_message::Headers> {
+        Ok(self.headers.clone())
     }
 }
@@ -0,0 +1,49 @@
+use std::fmt::Debug;
+
+/// A partition of a topic
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TopicPartition {
+    pub topic: String,
+    pub partition: i32,
+}
+
+///
+/// Observes the partitions assigned to the consumer by its group.
+/// The callbacks run on the thread that polls the consumer: they should return quickly,
+/// and no event is received while they run.
+///
+/// Example:
+/// ```rust,ignore
+/// #[derive(Debug)]
+/// struct CacheFlusher {
+///     caches: Arc<PartitionCaches>,
+/// }
+///
+/// impl RebalanceListener for CacheFlusher {
+///     fn on_revoked(&self, partitions: &[TopicPartition]) {
+///         self.caches.flush(partitions);
+///     }
+/// }
+///
+/// let consumer = kafka_consumer!(...)?.with_rebalance_listener(CacheFlusher { caches });
+/// ```
+///
+pub trait RebalanceListener: Debug + Send + Sync {
+    /// Called once the partitions are assigned to the consumer, before any of their events is received
+    #[allow(unused_variables)]
+    fn on_assigned(&self, partitions: &[TopicPartition]) {}
+
+    /// Called when the partitions are taken away from the consumer in a rebalance, before they are unassigned.
+    /// With `CommitStrategy::Batched` and `CommitStrategy::Auto`, the offsets of the processed events that are still pending
+    /// are committed right after the call. The other strategies commit every offset as soon as it can be committed:
+    /// with `CommitStrategy::Manual`, the events that were not acknowledged yet are consumed again by the next owner.
+    #[allow(unused_variables)]
+    fn on_revoked(&self, partitions: &[TopicPartition]) {}
+
+    /// Called instead of `on_revoked` when the consumer lost the partitions without a rebalance,
+    /// e.g. because its session timed out. The partitions may already be assigned to another consumer,
+    /// so their offsets can no longer be committed. Calls `on_revoked` by default.
+    fn on_lost(&self, partitions: &[TopicPartition]) {
+        self.on_revoked(partitions)
+    }
+}
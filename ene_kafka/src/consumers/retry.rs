@@ -0,0 +1,295 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use rand::Rng;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    dispatchers::EventDispatcher, errors::KafkaError,
+    messages::cloud_events::cloud_event::CloudEvent,
+};
+
+pub type RetryPredicate = Arc<dyn Fn(&KafkaError) -> bool + Send + Sync>;
+
+///
+/// Decides how often and how fast a failed event is dispatched again before it is given up on
+/// and sent to the dead letter queue.
+/// The backoff between two attempts grows exponentially from `initial_backoff` up to `max_backoff`.
+/// A random `jitter` fraction of the backoff is added or removed so that consumers do not retry in lockstep.
+///
+/// By default, an event is dispatched only once and only transient errors (see `KafkaError::is_transient`) are retried.
+/// Events whose handler asks for a retry with `HandlerOutcome::Retry` are retried in place up to `max_requested_attempts`
+/// times, whatever `max_attempts` and the retry predicate.
+///
+/// Example:
+/// ```rust,ignore
+/// let policy = RetryPolicy::exponential(5, Duration::from_millis(100), Duration::from_secs(10))
+///     .with_retry_predicate(|error| matches!(error, KafkaError::Handler(_)));
+/// let consumer = kafka_consumer!(...)?.with_retry_policy(policy);
+/// ```
+///
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of times an event is dispatched, including the first attempt
+    pub max_attempts: u32,
+    /// The maximum number of times an event is dispatched, including the first attempt,
+    /// while its handler keeps asking for a retry with `HandlerOutcome::Retry`
+    pub max_requested_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How much the backoff grows after every attempt, at least 1
+    pub multiplier: f64,
+    /// A fraction between 0 and 1 of the backoff that is randomly added or removed
+    pub jitter: f64,
+    retry_predicate: RetryPredicate,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            max_requested_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_predicate: Arc::new(KafkaError::is_transient),
+        }
+    }
+}
+
+impl Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("max_requested_attempts", &self.max_requested_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries errors, only the events whose handler asks for a retry with `HandlerOutcome::Retry`
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn exponential(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_max_requested_attempts(mut self, max_requested_attempts: u32) -> Self {
+        self.max_requested_attempts = max_requested_attempts;
+        self
+    }
+
+    /// The multiplier is raised to 1 if it is lower
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Only errors for which the predicate returns true are retried.
+    /// Retries requested by handlers with `HandlerOutcome::Retry` are not subject to it.
+    pub fn with_retry_predicate(
+        mut self,
+        retry_predicate: impl Fn(&KafkaError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_predicate = Arc::new(retry_predicate);
+        self
+    }
+
+    /// Returns true if the error is worth retrying at all, in place or through retry topics
+    pub fn is_retryable(&self, error: &KafkaError) -> bool {
+        match error {
+            // Handlers decide themselves whether these events must be retried
+            KafkaError::DeadLetter(_) | KafkaError::Fatal(_) => false,
+            KafkaError::RetryAfter(_) => true,
+            _ => (self.retry_predicate)(error),
+        }
+    }
+
+    /// Returns true if an event that failed `attempt` times with `error` should be dispatched again
+    pub fn should_retry(&self, error: &KafkaError, attempt: u32) -> bool {
+        let max_attempts = match error {
+            // The handler asked for the retry itself
+            KafkaError::RetryAfter(_) => self.max_requested_attempts,
+            _ => self.max_attempts,
+        };
+        attempt < max_attempts && self.is_retryable(error)
+    }
+
+    /// Returns how long to wait after the `attempt`th failed attempt, starting at 1.
+    /// The multiplier and the jitter are brought back in range if they were set out of it, see `with_multiplier` and `with_jitter`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        // `max` drops NaNs, unlike `clamp`
+        let multiplier = self.multiplier.max(1.0);
+        let jitter = if self.jitter.is_nan() {
+            0.0
+        } else {
+            self.jitter.clamp(0.0, 1.0)
+        };
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let growth = multiplier.powi(exponent).min(f64::MAX);
+        let backoff =
+            (self.initial_backoff.as_secs_f64() * growth).min(self.max_backoff.as_secs_f64());
+        let jitter = if jitter > 0.0 {
+            rand::thread_rng().gen_range(-jitter..=jitter)
+        } else {
+            0.0
+        };
+        Duration::try_from_secs_f64(backoff * (1.0 + jitter)).unwrap_or(self.max_backoff)
+    }
+}
+
+/// An event that could not be dispatched, even after retrying it in place
+#[derive(Debug)]
+pub struct FailedDispatch {
+    /// The error of the last attempt
+    pub error: KafkaError,
+    /// The number of times the event was dispatched
+    pub attempts: u32,
+}
+
+/// Dispatches an event and retries it in place according to the retry policy.
+/// Waiting for the next attempt is cut short if a shutdown is requested, in which case the last error is returned.
+pub async fn dispatch_with_retry<Dispatcher: EventDispatcher, Event: CloudEvent<String, String>>(
+    dispatcher: &Dispatcher,
+    event: &Event,
+    retry_policy: &RetryPolicy,
+    shutdown: &CancellationToken,
+) -> Result<(), FailedDispatch> {
+    match dispatcher.dispatch_event(event).await {
+        Ok(()) => Ok(()),
+        Err(error) => retry_failed_dispatch(dispatcher, event, retry_policy, shutdown, error).await,
+    }
+}
+
+/// Retries an event in place according to the retry policy, after its first dispatch failed with `error`
+pub async fn retry_failed_dispatch<
+    Dispatcher: EventDispatcher,
+    Event: CloudEvent<String, String>,
+>(
+    dispatcher: &Dispatcher,
+    event: &Event,
+    retry_policy: &RetryPolicy,
+    shutdown: &CancellationToken,
+    error: KafkaError,
+) -> Result<(), FailedDispatch> {
+    let mut attempt = 1;
+    let mut error = error;
+    while retry_policy.should_retry(&error, attempt) {
+        let backoff = error
+            .retry_after()
+            .unwrap_or_else(|| retry_policy.backoff(attempt));
+        tracing::warn!(
+            "Attempt {} to dispatch event failed, retrying in {:?}: {:?}",
+            attempt,
+            backoff,
+            error
+        );
+        tokio::select! {
+            _ = shutdown.cancelled() => return Err(FailedDispatch { error, attempts: attempt }),
+            _ = tokio::time::sleep(backoff) => {}
+        }
+        attempt += 1;
+        match dispatcher.dispatch_event(event).await {
+            Ok(()) => return Ok(()),
+            Err(next_error) => error = next_error,
+        }
+    }
+    Err(FailedDispatch {
+        error,
+        attempts: attempt,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::exponential(10, Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(0.0)
+    }
+
+    #[test]
+    fn grows_the_backoff_exponentially() {
+        let policy = policy().with_multiplier(3.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(300));
+        assert_eq!(policy.backoff(3), Duration::from_millis(900));
+    }
+
+    #[test]
+    fn caps_the_backoff() {
+        let policy = policy();
+
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn keeps_the_jitter_in_range() {
+        let policy = policy().with_jitter(0.5);
+
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_millis(50), "{backoff:?}");
+            assert!(backoff <= Duration::from_millis(150), "{backoff:?}");
+        }
+    }
+
+    #[test]
+    fn does_not_panic_on_out_of_range_settings() {
+        let mut policy = policy();
+        policy.multiplier = f64::NAN;
+        policy.jitter = f64::NAN;
+        assert_eq!(policy.backoff(3), Duration::from_millis(100));
+
+        policy.multiplier = -2.0;
+        policy.jitter = 7.0;
+        assert!(policy.backoff(2) <= Duration::from_millis(200));
+
+        let policy = RetryPolicy::exponential(10, Duration::MAX, Duration::MAX).with_jitter(1.0);
+        assert!(policy.backoff(u32::MAX) > Duration::ZERO);
+    }
+
+    #[test]
+    fn retries_requested_retries_whatever_the_predicate() {
+        let policy = RetryPolicy::exponential(10, Duration::ZERO, Duration::ZERO)
+            .with_max_requested_attempts(3)
+            .with_retry_predicate(|_| false);
+        let requested = KafkaError::RetryAfter(Duration::from_secs(1));
+
+        assert!(policy.should_retry(&requested, 2));
+        assert!(!policy.should_retry(&requested, 3));
+        assert!(!policy.should_retry(&KafkaError::Timeout("timed out".to_string()), 1));
+    }
+
+    #[test]
+    fn never_retries_dead_lettered_or_fatal_events() {
+        let policy = RetryPolicy::exponential(10, Duration::ZERO, Duration::ZERO)
+            .with_retry_predicate(|_| true);
+
+        assert!(!policy.should_retry(&KafkaError::DeadLetter("invalid".to_string()), 1));
+        assert!(!policy.should_retry(&KafkaError::Fatal("corrupted".to_string()), 1));
+    }
+}
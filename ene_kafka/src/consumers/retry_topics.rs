@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use crate::{
+    admins::KafkaAdminInterface,
+    messages::kafka_message::{Headers, KafkaTopic},
+    KafkaResult,
+};
+
+/// The prefix shared by all the headers added to events sent to retry topics
+pub const RETRY_HEADER_PREFIX: &str = "ene_retry_";
+/// The number of retry topics an event has already been sent through
+pub const RETRY_ATTEMPT_HEADER: &str = "ene_retry_attempt";
+/// The unix timestamp in milliseconds before which an event read from a retry topic must not be dispatched
+pub const RETRY_AFTER_HEADER: &str = "ene_retry_after";
+/// The topic the event was originally consumed from, before it was sent to the retry topics
+pub const RETRY_ORIGINAL_TOPIC_HEADER: &str = "ene_retry_original_topic";
+
+///
+/// A chain of delay topics that failed events go through before they end up in the dead letter queue.
+/// Unlike in-place retries, retry topics do not block the partition of the failed event:
+/// the event is produced to the next retry topic and picked up again by the same consumer once its delay has passed.
+///
+/// The retry topics are named after the consumed topic and their delay, e.g. `orders.retry.5s` and `orders.retry.1m`.
+///
+/// Example:
+/// ```rust,ignore
+/// let retry_topics = RetryTopics::new(vec![Duration::from_secs(5), Duration::from_secs(60)]);
+/// let consumer = kafka_consumer!(...)?.with_retry_topics(retry_topics);
+/// consumer.provision_retry_topics(&admin).await?;
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct RetryTopics {
+    pub delays: Vec<Duration>,
+    /// The number of partitions used when the retry topics are provisioned
+    pub partitions: i32,
+    /// The replication factor used when the retry topics are provisioned
+    pub replication_factor: i32,
+}
+
+impl RetryTopics {
+    pub fn new(delays: Vec<Duration>) -> Self {
+        Self {
+            delays,
+            partitions: 1,
+            replication_factor: 1,
+        }
+    }
+
+    pub fn with_partitions(mut self, partitions: i32) -> Self {
+        self.partitions = partitions;
+        self
+    }
+
+    pub fn with_replication_factor(mut self, replication_factor: i32) -> Self {
+        self.replication_factor = replication_factor;
+        self
+    }
+
+    /// Returns the name of the retry topic of `topic` for the given delay, e.g. `orders.retry.5s`
+    pub fn topic_name(topic: &str, delay: Duration) -> String {
+        format!("{}.retry.{}", topic, delay_label(delay))
+    }
+
+    /// Returns the topic a retry topic belongs to, e.g. `orders` for `orders.retry.5s`,
+    /// or `None` if `topic_name` is not a retry topic.
+    pub fn original_topic<'a>(&self, topic_name: &'a str) -> Option<&'a str> {
+        self.delays
+            .iter()
+            .find_map(|delay| topic_name.strip_suffix(&format!(".retry.{}", delay_label(*delay))))
+    }
+
+    /// Returns a regex matching the retry topics of the topics matched by `pattern`,
+    /// or `None` if there are no retry topics.
+    pub fn pattern(&self, pattern: &str) -> Option<String> {
+        if self.delays.is_empty() {
+            return None;
+        }
+        let topics = pattern.trim_start_matches('^').trim_end_matches('$');
+        let labels = self
+            .delays
+            .iter()
+            .map(|delay| delay_label(*delay))
+            .collect::<Vec<_>>()
+            .join("|");
+        Some(format!("^(?:{})\\.retry\\.(?:{})$", topics, labels))
+    }
+
+    /// Returns all the retry topics of `topic`, in the order they are used
+    pub fn topics(&self, topic: &KafkaTopic) -> Vec<KafkaTopic> {
+        self.delays
+            .iter()
+            .map(|delay| KafkaTopic {
+                name: Self::topic_name(&topic.name, *delay),
+                content_type: topic.content_type.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns true if `topic_name` is one of the retry topics of `topic`
+    pub fn is_retry_topic(&self, topic: &KafkaTopic, topic_name: &str) -> bool {
+        self.delays
+            .iter()
+            .any(|delay| Self::topic_name(&topic.name, *delay) == topic_name)
+    }
+
+    /// Returns the retry topic and its delay an event should be sent to after `attempt` retry topics,
+    /// or `None` if all retry topics have been used up.
+    pub fn next(&self, topic: &KafkaTopic, attempt: u32) -> Option<(KafkaTopic, Duration)> {
+        self.delays.get(attempt as usize).map(|delay| {
+            (
+                KafkaTopic {
+                    name: Self::topic_name(&topic.name, *delay),
+                    content_type: topic.content_type.clone(),
+                },
+                *delay,
+            )
+        })
+    }
+
+    /// Creates the retry topics of `topic` if they do not exist yet
+    pub async fn provision<Admin: KafkaAdminInterface + Sync>(
+        &self,
+        admin: &Admin,
+        topic: &KafkaTopic,
+    ) -> KafkaResult<()> {
+        for retry_topic in self.topics(topic) {
+            admin
+                .create_topic_if_not_exists(
+                    &retry_topic.name,
+                    self.partitions,
+                    self.replication_factor,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the suffix of the retry topic with this delay, e.g. `5s` or `1m`
+fn delay_label(delay: Duration) -> String {
+    let millis = delay.as_millis();
+    if millis == 0 {
+        "0s".to_string()
+    } else if millis % 3_600_000 == 0 {
+        format!("{}h", millis / 3_600_000)
+    } else if millis % 60_000 == 0 {
+        format!("{}m", millis / 60_000)
+    } else if millis % 1_000 == 0 {
+        format!("{}s", millis / 1_000)
+    } else {
+        format!("{}ms", millis)
+    }
+}
+
+/// Returns the number of retry topics the event with these headers has gone through
+pub fn retry_attempt(headers: &Headers) -> u32 {
+    headers
+        .get(RETRY_ATTEMPT_HEADER)
+        .and_then(|attempt| attempt.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Returns the unix timestamp in milliseconds before which the event with these headers must not be dispatched
+pub fn retry_after(headers: &Headers) -> Option<i64> {
+    headers
+        .get(RETRY_AFTER_HEADER)
+        .and_then(|retry_after| retry_after.parse().ok())
+}
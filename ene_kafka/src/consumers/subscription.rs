@@ -0,0 +1,112 @@
+use std::fmt::Display;
+
+use crate::messages::kafka_message::{ContentType, KafkaTopic};
+
+use super::retry_topics::RetryTopics;
+
+///
+/// The topics a consumer reads from: a list of topics, or all the topics whose name matches a regex.
+/// Pattern subscriptions pick up matching topics that are created after the consumer has started.
+///
+/// Example:
+/// ```rust,ignore
+/// let consumer = kafka_consumer!(
+///     topics = Subscription::pattern("^tenant-.*-orders$", ContentType::Json),
+///     ...
+/// )?;
+/// ```
+///
+#[derive(Debug, Clone)]
+pub enum Subscription {
+    Topics(Vec<KafkaTopic>),
+    Pattern {
+        /// A regex in the syntax supported by librdkafka, starting with `^`
+        pattern: String,
+        content_type: ContentType,
+    },
+}
+
+impl Subscription {
+    pub fn topics(topics: Vec<KafkaTopic>) -> Self {
+        Subscription::Topics(topics)
+    }
+
+    /// Subscribes to all the topics matching `pattern`. A leading `^` is added if it is missing.
+    pub fn pattern(pattern: impl Into<String>, content_type: ContentType) -> Self {
+        let pattern = pattern.into();
+        Subscription::Pattern {
+            pattern: if pattern.starts_with('^') {
+                pattern
+            } else {
+                format!("^{}", pattern)
+            },
+            content_type,
+        }
+    }
+
+    /// Returns the names and patterns to subscribe to, including the retry topics
+    pub fn subscribed_names(&self, retry_topics: Option<&RetryTopics>) -> Vec<String> {
+        match self {
+            Subscription::Topics(topics) => topics
+                .iter()
+                .flat_map(|topic| {
+                    std::iter::once(topic.name.clone()).chain(
+                        retry_topics
+                            .map(|retry_topics| retry_topics.topics(topic))
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|retry_topic| retry_topic.name),
+                    )
+                })
+                .collect(),
+            Subscription::Pattern { pattern, .. } => std::iter::once(pattern.clone())
+                .chain(retry_topics.and_then(|retry_topics| retry_topics.pattern(pattern)))
+                .collect(),
+        }
+    }
+
+    /// Returns the subscribed topic with this name
+    pub fn topic(&self, topic_name: &str) -> KafkaTopic {
+        match self {
+            Subscription::Topics(topics) => topics
+                .iter()
+                .find(|topic| topic.name == topic_name)
+                .cloned()
+                .unwrap_or_else(|| KafkaTopic {
+                    name: topic_name.to_string(),
+                    content_type: ContentType::Json,
+                }),
+            Subscription::Pattern { content_type, .. } => KafkaTopic {
+                name: topic_name.to_string(),
+                content_type: content_type.clone(),
+            },
+        }
+    }
+}
+
+impl From<KafkaTopic> for Subscription {
+    fn from(topic: KafkaTopic) -> Self {
+        Subscription::Topics(vec![topic])
+    }
+}
+
+impl From<Vec<KafkaTopic>> for Subscription {
+    fn from(topics: Vec<KafkaTopic>) -> Self {
+        Subscription::Topics(topics)
+    }
+}
+
+impl Display for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Subscription::Topics(topics) => {
+                let names = topics
+                    .iter()
+                    .map(|topic| topic.name.as_str())
+                    .collect::<Vec<_>>();
+                write!(f, "{}", names.join(", "))
+            }
+            Subscription::Pattern { pattern, .. } => write!(f, "{}", pattern),
+        }
+    }
+}
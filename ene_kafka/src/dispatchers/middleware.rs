@@ -0,0 +1,180 @@
+use std::{fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use futures::future::{join, join_all, BoxFuture};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    errors::KafkaError, messages::cloud_events::cloud_event::CloudEvent,
+    messages::kafka_message::Headers, KafkaResult,
+};
+
+use super::EventDispatcher;
+
+/// What a middleware knows about the event being dispatched
+#[derive(Debug, Clone)]
+pub struct DispatchContext {
+    /// The topic the event was received from
+    pub topic: String,
+    /// The key of the event, if it has one
+    pub key: Option<String>,
+    pub event_type: String,
+    /// The raw headers of the event, including the cloud event headers
+    pub headers: Headers,
+}
+
+impl DispatchContext {
+    pub fn from_event<Event: CloudEvent<String, String>>(event: &Event) -> KafkaResult<Self> {
+        Ok(Self {
+            topic: event.topic()?.name,
+            key: event.key().ok(),
+            event_type: event.event_type()?,
+            headers: event.headers()?,
+        })
+    }
+}
+
+/// The rest of the middleware chain, ending with the handler of the event
+pub struct Next<'a> {
+    run: Box<dyn FnOnce() -> BoxFuture<'a, KafkaResult<()>> + Send + 'a>,
+}
+
+impl<'a> Next<'a> {
+    fn new(run: impl FnOnce() -> BoxFuture<'a, KafkaResult<()>> + Send + 'a) -> Self {
+        Self { run: Box::new(run) }
+    }
+
+    /// Runs the next middlewares and the handler, and returns the result of the dispatch
+    pub async fn run(self) -> KafkaResult<()> {
+        (self.run)().await
+    }
+}
+
+///
+/// Runs around the dispatch of every event, e.g. to log, time, authorize or extract a tenant in one place
+/// rather than in every handler. A middleware calls `next.run()` to continue the dispatch, or returns without calling it
+/// to short-circuit the handlers: `Ok(())` marks the event as processed, an error goes through the retry policy
+/// and the dead letter queue like any other failure.
+/// Middlewares run in the order they were added to the consumer, the first one being the outermost.
+/// They run around the timeout of the consumer, and retried events go through them again.
+///
+/// The events of a batch go through the middlewares one at a time, but their `next.run()` only completes
+/// once the batch was dispatched. If a middleware short-circuits some events of a batch,
+/// the other events are dispatched one at a time.
+///
+/// Example:
+/// ```rust,ignore
+/// #[derive(Debug)]
+/// struct TenantAuthorizer;
+///
+/// #[async_trait]
+/// impl DispatchMiddleware for TenantAuthorizer {
+///     async fn dispatch(&self, context: &DispatchContext, next: Next<'_>) -> KafkaResult<()> {
+///         match context.headers.get("tenant") {
+///             Some(tenant) if is_allowed(tenant) => next.run().await,
+///             _ => Err(KafkaError::DeadLetter("unknown tenant".to_string())),
+///         }
+///     }
+/// }
+///
+/// let consumer = kafka_consumer!(...)?.with_middleware(TenantAuthorizer);
+/// ```
+///
+#[async_trait]
+pub trait DispatchMiddleware: Debug + Send + Sync {
+    async fn dispatch(&self, context: &DispatchContext, next: Next<'_>) -> KafkaResult<()>;
+}
+
+/// Runs every dispatch of the dispatcher it wraps through a chain of middlewares
+#[derive(Debug, Clone)]
+pub struct MiddlewareDispatcher<'a, Dispatcher: EventDispatcher> {
+    dispatcher: &'a Dispatcher,
+    middlewares: &'a [Arc<dyn DispatchMiddleware>],
+}
+
+impl<'a, Dispatcher: EventDispatcher> MiddlewareDispatcher<'a, Dispatcher> {
+    pub fn new(dispatcher: &'a Dispatcher, middlewares: &'a [Arc<dyn DispatchMiddleware>]) -> Self {
+        Self {
+            dispatcher,
+            middlewares,
+        }
+    }
+}
+
+fn run_chain<'a>(
+    middlewares: &'a [Arc<dyn DispatchMiddleware>],
+    context: &'a DispatchContext,
+    handler: Next<'a>,
+) -> BoxFuture<'a, KafkaResult<()>> {
+    match middlewares.split_first() {
+        Some((middleware, rest)) => middleware.dispatch(
+            context,
+            Next::new(move || run_chain(rest, context, handler)),
+        ),
+        None => Box::pin(handler.run()),
+    }
+}
+
+#[async_trait]
+impl<Dispatcher: EventDispatcher> EventDispatcher for MiddlewareDispatcher<'_, Dispatcher> {
+    async fn dispatch_event<Event: CloudEvent<String, String>>(
+        &self,
+        event: &Event,
+    ) -> KafkaResult<()> {
+        if self.middlewares.is_empty() {
+            return self.dispatcher.dispatch_event(event).await;
+        }
+        let context = DispatchContext::from_event(event)?;
+        let handler = Next::new(move || self.dispatcher.dispatch_event(event));
+        run_chain(self.middlewares, &context, handler).await
+    }
+
+    async fn dispatch_batch<Event: CloudEvent<String, String>>(
+        &self,
+        events: &[Event],
+    ) -> Vec<KafkaResult<()>> {
+        if self.middlewares.is_empty() {
+            return self.dispatcher.dispatch_batch(events).await;
+        }
+        // Every event that reaches the end of the chain is admitted to the batch, and waits for its result
+        let (admissions, mut admitted) = mpsc::unbounded_channel();
+        let chains = join_all(events.iter().enumerate().map(|(index, event)| {
+            let admissions = admissions.clone();
+            async move {
+                let context = DispatchContext::from_event(event)?;
+                let handler = Next::new(move || {
+                    Box::pin(async move {
+                        let (result_sender, result) = oneshot::channel();
+                        let _ = admissions.send((index, result_sender));
+                        drop(admissions);
+                        result.await.unwrap_or_else(|_| {
+                            Err(KafkaError::Handler(anyhow::anyhow!(
+                                "The batch was not dispatched"
+                            )))
+                        })
+                    })
+                });
+                run_chain(self.middlewares, &context, handler).await
+            }
+        }));
+        drop(admissions);
+        let dispatching = async {
+            let mut senders = Vec::with_capacity(events.len());
+            while let Some(admission) = admitted.recv().await {
+                senders.push(admission);
+            }
+            if senders.len() == events.len() {
+                senders.sort_by_key(|(index, _)| *index);
+                let results = self.dispatcher.dispatch_batch(events).await;
+                for ((_, sender), result) in senders.into_iter().zip(results) {
+                    let _ = sender.send(result);
+                }
+            } else {
+                for (index, sender) in senders {
+                    let _ = sender.send(self.dispatcher.dispatch_event(&events[index]).await);
+                }
+            }
+        };
+        join(chains, dispatching).await.0
+    }
+}
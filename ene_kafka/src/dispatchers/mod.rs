@@ -1,40 +1,244 @@
+pub mod middleware;
+pub mod routes;
+#[cfg(feature = "tower")]
+pub mod service;
+
+use std::time::Duration;
+
 use async_trait::async_trait;
 
-use crate::messages::cloud_events::cloud_event::CloudEvent;
+use crate::{
+    errors::KafkaError, handlers::with_timeout, messages::cloud_events::cloud_event::CloudEvent,
+    KafkaResult,
+};
 
 #[async_trait]
 pub trait EventDispatcher: Send + Sync {
     async fn dispatch_event<Event: CloudEvent<String, String>>(
         &self,
         event: &Event,
-    ) -> anyhow::Result<()>;
+    ) -> KafkaResult<()>;
+
+    /// Dispatches a batch of events and returns the result of every event, in the same order.
+    /// By default, the events are dispatched one at a time.
+    async fn dispatch_batch<Event: CloudEvent<String, String>>(
+        &self,
+        events: &[Event],
+    ) -> Vec<KafkaResult<()>> {
+        let mut results = Vec::with_capacity(events.len());
+        for event in events {
+            results.push(self.dispatch_event(event).await);
+        }
+        results
+    }
+}
+
+/// Enforces a timeout on every dispatch of the dispatcher it wraps
+#[derive(Debug, Clone)]
+pub struct TimeoutDispatcher<'a, Dispatcher: EventDispatcher> {
+    dispatcher: &'a Dispatcher,
+    timeout: Option<Duration>,
+}
+
+impl<'a, Dispatcher: EventDispatcher> TimeoutDispatcher<'a, Dispatcher> {
+    pub fn new(dispatcher: &'a Dispatcher, timeout: Option<Duration>) -> Self {
+        Self {
+            dispatcher,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl<Dispatcher: EventDispatcher> EventDispatcher for TimeoutDispatcher<'_, Dispatcher> {
+    async fn dispatch_event<Event: CloudEvent<String, String>>(
+        &self,
+        event: &Event,
+    ) -> KafkaResult<()> {
+        with_timeout(self.timeout, self.dispatcher.dispatch_event(event)).await
+    }
+
+    /// Every event of the batch fails with `KafkaError::HandlerTimeout` if the batch times out
+    async fn dispatch_batch<Event: CloudEvent<String, String>>(
+        &self,
+        events: &[Event],
+    ) -> Vec<KafkaResult<()>> {
+        let dispatching = async { Ok(self.dispatcher.dispatch_batch(events).await) };
+        with_timeout(self.timeout, dispatching)
+            .await
+            .unwrap_or_else(|_| {
+                events
+                    .iter()
+                    .map(|_| Err(KafkaError::HandlerTimeout(self.timeout.unwrap_or_default())))
+                    .collect()
+            })
+    }
 }
 
 /// A macro to generate an event dispatcher struct that will dispatch events to the appropriate handlers
 /// based on the event type.
-/// The macro expects a list of handlers that will be used to dispatch the events.
+/// The macro expects a list of handlers that will be used to dispatch the events,
+/// optionally followed by a list of batch handlers that receive the events they can handle in batches.
+/// The dispatcher is created with `CloudEventDispatcher::new`, taking the handlers in the same order.
+/// It reads the headers of every event once and looks its handler up by event type and topic in a map built by `new`,
+/// which returns `KafkaError::Config` if several handlers handle the same event type from the same topics.
+/// The handler found is only dispatched the event if its `can_handle_route` accepts it, given the event type and topic already read.
+///
+/// Example:
+/// ```rust,ignore
+/// generate_event_dispatcher!(
+///     handlers = {entity_created_handler: EntityCreatedHandler},
+///     batch_handlers = {entity_updated_batch_handler: EntityUpdatedBatchHandler}
+/// );
+/// let dispatcher = CloudEventDispatcher::new(EntityCreatedHandler {}, EntityUpdatedBatchHandler {})?;
+/// ```
 #[macro_export]
 macro_rules! generate_event_dispatcher {
-    ($($handler_name: ident: $handler_type: ident $(< $( $generic_identifier:tt $( : $identifier_constraint:tt $(+ $identifier_additions:tt )* )? ),+ >)?),*) => {
+    (
+        handlers = {$($handler_name: ident: $handler_type: ident $(< $( $generic_identifier:tt $( : $identifier_constraint:tt $(+ $identifier_additions:tt )* )? ),+ >)?),*},
+        batch_handlers = {$($batch_handler_name: ident: $batch_handler_type: ident),*}
+    ) => {
         struct CloudEventDispatcher {
            $(
                $handler_name: $handler_type $(< $( $generic_identifier $( : $identifier_constraint $(+ $identifier_additions )* )? ),+ >)?,
            )*
+           $(
+               $batch_handler_name: $batch_handler_type,
+           )*
+           routes: ene_kafka::dispatchers::routes::Routes<CloudEventRoute>,
+        }
+
+        /// The handler an event is routed to
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum CloudEventRoute {
+            $($handler_name,)*
+            $($batch_handler_name,)*
         }
 
+        impl CloudEventDispatcher {
+            #[allow(clippy::too_many_arguments)]
+            fn new(
+                $($handler_name: $handler_type $(< $( $generic_identifier $( : $identifier_constraint $(+ $identifier_additions )* )? ),+ >)?,)*
+                $($batch_handler_name: $batch_handler_type,)*
+            ) -> ene_kafka::KafkaResult<Self> {
+                #[allow(unused_mut)]
+                let mut routes = ene_kafka::dispatchers::routes::Routes::default();
+                $(
+                    routes.add_handler(CloudEventRoute::$handler_name, &$handler_name)?;
+                )*
+                $(
+                    routes.add_batch_handler(CloudEventRoute::$batch_handler_name, &$batch_handler_name)?;
+                )*
+                Ok(Self {
+                    $($handler_name,)*
+                    $($batch_handler_name,)*
+                    routes,
+                })
+            }
+
+            /// Dispatches the event to its handler, given its event type and source topic
+            async fn dispatch_routed<Event: ene_kafka::messages::cloud_events::cloud_event::CloudEvent<String, String>>(
+                &self,
+                event: &Event,
+                event_type: ene_kafka::messages::cloud_events::cloud_event::EventType,
+                topic: &str,
+            ) -> ene_kafka::KafkaResult<()> {
+                #[allow(unused_imports)]
+                use ene_kafka::handlers::{BatchEventHandler, EventHandler};
+                match self.routes.route(&event_type, topic) {
+                    $(
+                        Some(CloudEventRoute::$handler_name) if self.$handler_name.can_handle_route(event, &event_type, topic)? => {
+                            ene_kafka::handlers::with_timeout(
+                                EventHandler::<Event, _>::timeout(&self.$handler_name),
+                                self.$handler_name.deserialize_and_handle(event),
+                            ).await
+                        }
+                    )*
+                    $(
+                        Some(CloudEventRoute::$batch_handler_name) if self.$batch_handler_name.can_handle_route(event, &event_type, topic)? => {
+                            ene_kafka::handlers::with_timeout(
+                                BatchEventHandler::<Event, _>::timeout(&self.$batch_handler_name),
+                                async {
+                                    self.$batch_handler_name.deserialize_and_handle_batch(&[event]).await.pop().unwrap_or_else(ene_kafka::handlers::missing_batch_result)
+                                },
+                            ).await
+                        }
+                    )*
+                    #[allow(unreachable_patterns)]
+                    _ => Err(ene_kafka::errors::KafkaError::NoHandler(event_type)),
+                }
+            }
+        }
 
     #[async_trait::async_trait]
     impl ene_kafka::dispatchers::EventDispatcher for CloudEventDispatcher {
 
-        async fn dispatch_event<Event: ene_kafka::messages::cloud_events::cloud_event::CloudEvent<String, String>>(&self, event: &Event) -> anyhow::Result<()> {
-            use ene_kafka::handlers::EventHandler;
+        async fn dispatch_event<Event: ene_kafka::messages::cloud_events::cloud_event::CloudEvent<String, String>>(&self, event: &Event) -> ene_kafka::KafkaResult<()> {
+            let (event_type, topic) = ene_kafka::dispatchers::routes::route_key(event)?;
+            self.dispatch_routed(event, event_type, &topic).await
+        }
+
+        async fn dispatch_batch<Event: ene_kafka::messages::cloud_events::cloud_event::CloudEvent<String, String>>(&self, events: &[Event]) -> Vec<ene_kafka::KafkaResult<()>> {
+            #[allow(unused_imports)]
+            use ene_kafka::handlers::BatchEventHandler;
+            let keys = events
+                .iter()
+                .map(|event| ene_kafka::dispatchers::routes::route_key(event).ok())
+                .collect::<Vec<_>>();
+            #[allow(unused_variables)]
+            let routes = keys
+                .iter()
+                .map(|key| key.as_ref().and_then(|(event_type, topic)| self.routes.route(event_type, topic)))
+                .collect::<Vec<_>>();
+            #[allow(unused_mut)]
+            let mut results: Vec<Option<ene_kafka::KafkaResult<()>>> = events.iter().map(|_| None).collect();
             $(
-                if self.$handler_name.can_handle(event)? {
-                    return self.$handler_name.deserialize_and_handle(event).await;
+                let indices = (0..events.len())
+                    .filter(|index| {
+                        routes[*index] == Some(CloudEventRoute::$batch_handler_name)
+                            && keys[*index].as_ref().is_some_and(|(event_type, topic)| {
+                                matches!(self.$batch_handler_name.can_handle_route(&events[*index], event_type, topic), Ok(true))
+                            })
+                    })
+                    .collect::<Vec<_>>();
+                if !indices.is_empty() {
+                    let batch = indices.iter().map(|index| &events[*index]).collect::<Vec<_>>();
+                    let timeout = BatchEventHandler::<Event, _>::timeout(&self.$batch_handler_name);
+                    let batch_results = ene_kafka::handlers::with_timeout(
+                        timeout,
+                        async { Ok(self.$batch_handler_name.deserialize_and_handle_batch(&batch).await) },
+                    ).await;
+                    match batch_results {
+                        Ok(batch_results) => {
+                            for (index, result) in indices.into_iter().zip(batch_results) {
+                                results[index] = Some(result);
+                            }
+                        }
+                        Err(_) => {
+                            for index in indices {
+                                results[index] = Some(Err(ene_kafka::errors::KafkaError::HandlerTimeout(timeout.unwrap_or_default())));
+                            }
+                        }
+                    }
                 }
             )*
-            anyhow::bail!("No handler found for event type {:?}", event.event_type()?);
+            let mut dispatched = Vec::with_capacity(events.len());
+            for ((event, result), key) in events.iter().zip(results).zip(keys) {
+                match (result, key) {
+                    (Some(result), _) => dispatched.push(result),
+                    (None, Some((event_type, topic))) => dispatched.push(self.dispatch_routed(event, event_type, &topic).await),
+                    (None, None) => dispatched.push(self.dispatch_event(event).await),
+                }
+            }
+            dispatched
         }
     }
-    }
+    };
+    ($($handler_name: ident: $handler_type: ident $(< $( $generic_identifier:tt $( : $identifier_constraint:tt $(+ $identifier_additions:tt )* )? ),+ >)?),*) => {
+        ene_kafka::generate_event_dispatcher!(
+            handlers = {$($handler_name: $handler_type $(< $( $generic_identifier $( : $identifier_constraint $(+ $identifier_additions )* )? ),+ >)?),*},
+            batch_handlers = {}
+        );
+    };
 }
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use rdkafka::message::OwnedMessage;
+
+use crate::{
+    errors::KafkaError,
+    handlers::{source_topic_in, BatchEventHandler, EventHandler},
+    messages::cloud_events::cloud_event::{CloudEvent, DeserializeFrom, EventType},
+    KafkaResult,
+};
+
+///
+/// The handler of every event type of a dispatcher, computed once when the dispatcher is created,
+/// so that an event is routed by reading its headers once instead of asking every handler whether it can handle it.
+/// Several handlers can handle the same event type as long as they handle it from different topics.
+///
+#[derive(Debug, Clone)]
+pub struct Routes<Route> {
+    routes: HashMap<EventType, Vec<TopicRoute<Route>>>,
+}
+
+#[derive(Debug, Clone)]
+struct TopicRoute<Route> {
+    route: Route,
+    topics: Vec<String>,
+}
+
+impl<Route> Default for Routes<Route> {
+    fn default() -> Self {
+        Self {
+            routes: HashMap::new(),
+        }
+    }
+}
+
+// The handlers of a dispatcher handle events of every type, since `EventDispatcher::dispatch_event` is generic over it.
+// Their event type and topics are read through the events the consumer dispatches.
+type AnyEvent = OwnedMessage;
+
+impl<Route: Copy> Routes<Route> {
+    /// Routes the events of the handler's type to `route`.
+    /// Returns `KafkaError::Config` if another handler already handles events of this type from the same topics.
+    pub fn add_handler<Handler, HandlableEvent>(
+        &mut self,
+        route: Route,
+        handler: &Handler,
+    ) -> KafkaResult<()>
+    where
+        Handler: EventHandler<AnyEvent, HandlableEvent>,
+        HandlableEvent: CloudEvent<String, String> + DeserializeFrom<String, String, AnyEvent>,
+    {
+        self.add(route, handler.event_type()?, handler.topics())
+    }
+
+    /// Routes the events of the batch handler's type to `route`, see `add_handler`
+    pub fn add_batch_handler<Handler, HandlableEvent>(
+        &mut self,
+        route: Route,
+        handler: &Handler,
+    ) -> KafkaResult<()>
+    where
+        Handler: BatchEventHandler<AnyEvent, HandlableEvent>,
+        HandlableEvent: CloudEvent<String, String> + DeserializeFrom<String, String, AnyEvent>,
+    {
+        self.add(route, handler.event_type()?, handler.topics())
+    }
+
+    fn add(&mut self, route: Route, event_type: EventType, topics: Vec<String>) -> KafkaResult<()> {
+        let routes = self.routes.entry(event_type.clone()).or_default();
+        if routes
+            .iter()
+            .any(|existing| overlap(&existing.topics, &topics))
+        {
+            return Err(KafkaError::Config(format!(
+                "Several handlers handle events of type {event_type:?} from the same topics"
+            )));
+        }
+        routes.push(TopicRoute { route, topics });
+        Ok(())
+    }
+
+    /// Returns the route of the events of `event_type` from `topic`, or `None` if no handler handles them
+    pub fn route(&self, event_type: &str, topic: &str) -> Option<Route> {
+        self.routes
+            .get(event_type)?
+            .iter()
+            .find(|route| route.topics.is_empty() || route.topics.iter().any(|t| t == topic))
+            .map(|route| route.route)
+    }
+}
+
+/// Returns the event type and the source topic of the event, reading its headers once
+pub fn route_key<Event: CloudEvent<String, String>>(
+    event: &Event,
+) -> KafkaResult<(EventType, String)> {
+    let headers = event.headers()?;
+    let event_type = match headers.get("ce_type") {
+        Some(event_type) => event_type.clone(),
+        None => event.event_type()?,
+    };
+    Ok((event_type, source_topic_in(event, &headers)?))
+}
+
+/// Returns true if both lists of topics have a topic in common, an empty list standing for every topic
+fn overlap(topics: &[String], other_topics: &[String]) -> bool {
+    topics.is_empty()
+        || other_topics.is_empty()
+        || topics.iter().any(|topic| other_topics.contains(topic))
+}
@@ -0,0 +1,268 @@
+use std::{marker::PhantomData, sync::Arc, task::Poll, time::Instant};
+
+use async_trait::async_trait;
+use futures::future::{poll_fn, BoxFuture};
+use tower::{timeout::error::Elapsed, Service};
+
+use crate::{
+    errors::KafkaError,
+    handlers::{with_timeout, EventHandler},
+    messages::{
+        cloud_events::cloud_event::{CloudEvent, DeserializeFrom},
+        kafka_message::{Headers, KafkaMessage, KafkaTopic},
+    },
+    KafkaResult,
+};
+
+use super::EventDispatcher;
+
+/// The errors returned by tower layers, e.g. `tower::load_shed::error::Overloaded`
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+///
+/// An owned copy of a consumed event, the request of the `tower::Service`s that consumers dispatch events to.
+///
+#[derive(Debug, Clone)]
+pub struct CloudEventRequest {
+    topic: KafkaTopic,
+    key: Option<String>,
+    payload: Option<String>,
+    headers: Headers,
+}
+
+impl CloudEventRequest {
+    pub fn from_event<Event: CloudEvent<String, String>>(event: &Event) -> KafkaResult<Self> {
+        Ok(Self {
+            topic: event.topic()?,
+            key: event.key().ok(),
+            payload: event.payload().ok(),
+            headers: event.headers()?,
+        })
+    }
+
+    fn header(&self, name: &str) -> KafkaResult<String> {
+        self.headers
+            .get(name)
+            .cloned()
+            .ok_or(KafkaError::MissingHeader(name.to_string()))
+    }
+}
+
+impl KafkaMessage<String, String> for CloudEventRequest {
+    fn topic(&self) -> KafkaResult<KafkaTopic> {
+        Ok(self.topic.clone())
+    }
+
+    fn payload(&self) -> KafkaResult<String> {
+        self.payload.clone().ok_or(KafkaError::Deserialization(
+            "Message has no payload".to_string(),
+        ))
+    }
+
+    fn key(&self) -> KafkaResult<String> {
+        self.key.clone().ok_or(KafkaError::Deserialization(
+            "Message has no key".to_string(),
+        ))
+    }
+
+    fn headers(&self) -> KafkaResult<Headers> {
+        Ok(self.headers.clone())
+    }
+}
+
+impl CloudEvent<String, String> for CloudEventRequest {
+    fn spec_version(&self) -> KafkaResult<String> {
+        self.header("ce_specversion")
+    }
+
+    fn event_type(&self) -> KafkaResult<String> {
+        self.header("ce_type")
+    }
+
+    fn event_source(&self) -> KafkaResult<String> {
+        self.header("ce_source")
+    }
+
+    fn event_id(&self) -> KafkaResult<String> {
+        self.header("ce_id")
+    }
+
+    fn event_time(&self) -> KafkaResult<String> {
+        self.header("ce_time")
+    }
+
+    fn event_content_type(&self) -> KafkaResult<String> {
+        self.header("content_type")
+    }
+
+    fn entity_event_type() -> KafkaResult<String> {
+        Ok(String::from("ene_kafka.CloudEventRequest"))
+    }
+}
+
+///
+/// Turns an `EventHandler` into a `tower::Service`, to put tower layers in front of a single handler.
+/// Events the handler cannot handle fail with `KafkaError::NoHandler`.
+///
+pub struct HandlerService<Handler, HandlableEvent> {
+    handler: Arc<Handler>,
+    events: PhantomData<fn() -> HandlableEvent>,
+}
+
+impl<Handler, HandlableEvent> HandlerService<Handler, HandlableEvent> {
+    pub fn new(handler: Handler) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            events: PhantomData,
+        }
+    }
+}
+
+impl<Handler, HandlableEvent> Clone for HandlerService<Handler, HandlableEvent> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            events: PhantomData,
+        }
+    }
+}
+
+impl<Event, Handler, HandlableEvent> Service<Event> for HandlerService<Handler, HandlableEvent>
+where
+    Event: CloudEvent<String, String> + 'static,
+    HandlableEvent: CloudEvent<String, String> + DeserializeFrom<String, String, Event> + 'static,
+    Handler: EventHandler<Event, HandlableEvent> + Send + Sync + 'static,
+{
+    type Response = ();
+    type Error = KafkaError;
+    type Future = BoxFuture<'static, KafkaResult<()>>;
+
+    fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<KafkaResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, event: Event) -> Self::Future {
+        let handler = self.handler.clone();
+        Box::pin(async move {
+            if !handler.can_handle(&event)? {
+                return Err(KafkaError::NoHandler(event.event_type()?));
+            }
+            with_timeout(handler.timeout(), handler.deserialize_and_handle(&event)).await
+        })
+    }
+}
+
+///
+/// Turns an `EventDispatcher`, e.g. the one generated by `generate_event_dispatcher!`, into a `tower::Service`.
+///
+pub struct DispatcherService<Dispatcher> {
+    dispatcher: Arc<Dispatcher>,
+}
+
+impl<Dispatcher> DispatcherService<Dispatcher> {
+    pub fn new(dispatcher: Dispatcher) -> Self {
+        Self {
+            dispatcher: Arc::new(dispatcher),
+        }
+    }
+}
+
+impl<Dispatcher> Clone for DispatcherService<Dispatcher> {
+    fn clone(&self) -> Self {
+        Self {
+            dispatcher: self.dispatcher.clone(),
+        }
+    }
+}
+
+impl<Event, Dispatcher> Service<Event> for DispatcherService<Dispatcher>
+where
+    Event: CloudEvent<String, String> + 'static,
+    Dispatcher: EventDispatcher + 'static,
+{
+    type Response = ();
+    type Error = KafkaError;
+    type Future = BoxFuture<'static, KafkaResult<()>>;
+
+    fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<KafkaResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, event: Event) -> Self::Future {
+        let dispatcher = self.dispatcher.clone();
+        Box::pin(async move { dispatcher.dispatch_event(&event).await })
+    }
+}
+
+///
+/// Dispatches every event to a `tower::Service`, so that tower layers like timeouts, rate limits,
+/// concurrency limits or load shedding can be reused with Kafka consumers.
+/// Every event is dispatched to its own clone of the service, so that events are dispatched concurrently.
+/// Layers that share their state between clones, like `ConcurrencyLimitLayer`, apply to the whole consumer.
+/// Layers that keep their state per clone, like `RateLimitLayer`, must be put behind a `BufferLayer` to do so.
+/// The `Elapsed` error of `TimeoutLayer` becomes `KafkaError::HandlerTimeout`, like the timeouts of the handlers.
+/// The other errors of the layers that are not a `KafkaError`, e.g. `Overloaded`, are wrapped in `KafkaError::Handler`
+/// and retried like any other transient error.
+///
+/// Example:
+/// ```rust,ignore
+/// generate_event_dispatcher!(entity_created_handler: EntityCreatedHandler);
+/// let service = ServiceBuilder::new()
+///     .load_shed()
+///     .concurrency_limit(64)
+///     .timeout(Duration::from_secs(5))
+///     .service(DispatcherService::new(CloudEventDispatcher::new(EntityCreatedHandler {})?));
+/// let consumer = kafka_consumer!(
+///     topic = ...,
+///     dlq_topic = ...,
+///     consumer_group_id = "test-group",
+///     bootstrap_servers = "localhost:9092",
+///     service = service
+/// )?;
+/// ```
+///
+pub struct ServiceDispatcher<S> {
+    service: S,
+}
+
+impl<S> ServiceDispatcher<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
+/// Converts the error of a layer into a `KafkaError`, `started` being when the dispatch started
+fn into_kafka_error(error: impl Into<BoxError>, started: Instant) -> KafkaError {
+    let error = match error.into().downcast::<KafkaError>() {
+        Ok(error) => return *error,
+        Err(error) => error,
+    };
+    match error.downcast::<Elapsed>() {
+        Ok(_) => KafkaError::HandlerTimeout(started.elapsed()),
+        Err(error) => KafkaError::Handler(anyhow::anyhow!(error)),
+    }
+}
+
+#[async_trait]
+impl<S> EventDispatcher for ServiceDispatcher<S>
+where
+    S: Service<CloudEventRequest, Response = ()> + Clone + Send + Sync,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+{
+    async fn dispatch_event<Event: CloudEvent<String, String>>(
+        &self,
+        event: &Event,
+    ) -> KafkaResult<()> {
+        let request = CloudEventRequest::from_event(event)?;
+        let started = Instant::now();
+        let mut service = self.service.clone();
+        poll_fn(|context| service.poll_ready(context))
+            .await
+            .map_err(|error| into_kafka_error(error, started))?;
+        service
+            .call(request)
+            .await
+            .map_err(|error| into_kafka_error(error, started))
+    }
+}
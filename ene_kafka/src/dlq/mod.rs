@@ -0,0 +1,177 @@
+pub mod rdkafka_impl;
+pub mod redriver;
+
+use std::{error::Error, time::Duration};
+
+use crate::{errors::KafkaError, messages::kafka_message::Headers};
+
+/// The prefix shared by all the headers added to dead-lettered events
+pub const DLQ_HEADER_PREFIX: &str = "ene_dlq_";
+/// The topic the dead-lettered event was originally consumed from
+pub const DLQ_ORIGINAL_TOPIC_HEADER: &str = "ene_dlq_original_topic";
+/// The partition the dead-lettered event was consumed from
+pub const DLQ_ORIGINAL_PARTITION_HEADER: &str = "ene_dlq_original_partition";
+/// The offset the dead-lettered event was consumed from
+pub const DLQ_ORIGINAL_OFFSET_HEADER: &str = "ene_dlq_original_offset";
+/// The timestamp of the consumed record, in milliseconds since the unix epoch
+pub const DLQ_ORIGINAL_TIMESTAMP_HEADER: &str = "ene_dlq_original_timestamp";
+/// The group of the consumer that gave up on the event
+pub const DLQ_CONSUMER_GROUP_HEADER: &str = "ene_dlq_consumer_group";
+/// The error that made the last dispatch attempt fail
+pub const DLQ_ERROR_HEADER: &str = "ene_dlq_error";
+/// The error and all of its sources, one per line
+pub const DLQ_ERROR_CHAIN_HEADER: &str = "ene_dlq_error_chain";
+/// The number of times the event was dispatched before it was dead-lettered
+pub const DLQ_ATTEMPTS_HEADER: &str = "ene_dlq_attempts";
+/// When the event was dead-lettered, as an RFC 3339 timestamp
+pub const DLQ_FAILED_AT_HEADER: &str = "ene_dlq_failed_at";
+
+///
+/// What the consumer does when a failed event cannot be forwarded to its retry topic or to the dead letter queue.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DlqFailureMode {
+    /// The failure is logged and the offset of the event is committed anyway, so the event is lost.
+    #[default]
+    LogAndCommit,
+    /// The consumer stops processing events and keeps forwarding the event with an exponential backoff
+    /// until it succeeds. The offset is only committed once the event has been forwarded,
+    /// which gives at-least-once delivery from the consumed topic to the dead letter queue.
+    /// If the consumer is shut down in the meantime, the event is left uncommitted and consumed again after a restart.
+    /// Events that can never be forwarded, e.g. because they are too large for the topic (see `KafkaError::is_transient`),
+    /// are not retried: the consumer stops without committing them and `KafkaConsumer::start` returns `KafkaError::Fatal`.
+    Block {
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    },
+}
+
+impl DlqFailureMode {
+    /// Blocks with a backoff growing from 100ms up to 30s
+    pub fn block() -> Self {
+        DlqFailureMode::Block {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+///
+/// Why and where an event failed, attached as headers to the records sent to the dead letter queue.
+/// The original headers of the event are kept next to these.
+///
+/// When the event failed after going through retry topics, `original_topic` is the topic it was first consumed from,
+/// while `original_partition` and `original_offset` point to the record of the last retry topic.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetterMetadata {
+    pub original_topic: String,
+    pub original_partition: i32,
+    pub original_offset: i64,
+    pub original_timestamp: Option<i64>,
+    pub consumer_group: String,
+    pub error: String,
+    pub error_chain: Vec<String>,
+    pub attempts: u32,
+    pub failed_at: String,
+}
+
+impl DeadLetterMetadata {
+    pub fn new(
+        original_topic: String,
+        original_partition: i32,
+        original_offset: i64,
+        original_timestamp: Option<i64>,
+        consumer_group: String,
+        error: &KafkaError,
+        attempts: u32,
+    ) -> Self {
+        Self {
+            original_topic,
+            original_partition,
+            original_offset,
+            original_timestamp,
+            consumer_group,
+            error: error.to_string(),
+            error_chain: error_chain(error),
+            attempts,
+            failed_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn to_headers(&self) -> Headers {
+        let mut headers = Headers::from([
+            (
+                DLQ_ORIGINAL_TOPIC_HEADER.to_string(),
+                self.original_topic.clone(),
+            ),
+            (
+                DLQ_ORIGINAL_PARTITION_HEADER.to_string(),
+                self.original_partition.to_string(),
+            ),
+            (
+                DLQ_ORIGINAL_OFFSET_HEADER.to_string(),
+                self.original_offset.to_string(),
+            ),
+            (
+                DLQ_CONSUMER_GROUP_HEADER.to_string(),
+                self.consumer_group.clone(),
+            ),
+            (DLQ_ERROR_HEADER.to_string(), self.error.clone()),
+            (
+                DLQ_ERROR_CHAIN_HEADER.to_string(),
+                self.error_chain.join("\n"),
+            ),
+            (DLQ_ATTEMPTS_HEADER.to_string(), self.attempts.to_string()),
+            (DLQ_FAILED_AT_HEADER.to_string(), self.failed_at.clone()),
+        ]);
+        if let Some(original_timestamp) = self.original_timestamp {
+            headers.insert(
+                DLQ_ORIGINAL_TIMESTAMP_HEADER.to_string(),
+                original_timestamp.to_string(),
+            );
+        }
+        headers
+    }
+
+    /// Reads the metadata back from the headers of a dead-lettered record.
+    /// Returns `None` if the record was not enriched by Ene Kafka.
+    pub fn from_headers(headers: &Headers) -> Option<Self> {
+        Some(Self {
+            original_topic: headers.get(DLQ_ORIGINAL_TOPIC_HEADER)?.clone(),
+            original_partition: headers.get(DLQ_ORIGINAL_PARTITION_HEADER)?.parse().ok()?,
+            original_offset: headers.get(DLQ_ORIGINAL_OFFSET_HEADER)?.parse().ok()?,
+            original_timestamp: headers
+                .get(DLQ_ORIGINAL_TIMESTAMP_HEADER)
+                .and_then(|timestamp| timestamp.parse().ok()),
+            consumer_group: headers
+                .get(DLQ_CONSUMER_GROUP_HEADER)
+                .cloned()
+                .unwrap_or_default(),
+            error: headers.get(DLQ_ERROR_HEADER).cloned().unwrap_or_default(),
+            error_chain: headers
+                .get(DLQ_ERROR_CHAIN_HEADER)
+                .map(|chain| chain.lines().map(str::to_string).collect())
+                .unwrap_or_default(),
+            attempts: headers
+                .get(DLQ_ATTEMPTS_HEADER)
+                .and_then(|attempts| attempts.parse().ok())
+                .unwrap_or_default(),
+            failed_at: headers
+                .get(DLQ_FAILED_AT_HEADER)
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Returns the messages of the error and all of its sources, starting with the error itself
+fn error_chain(error: &KafkaError) -> Vec<String> {
+    let mut chain = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        chain.push(cause.to_string());
+        source = cause.source();
+    }
+    chain
+}
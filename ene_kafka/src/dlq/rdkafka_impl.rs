@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::BorrowedMessage;
+use rdkafka::{Message, Offset, TopicPartitionList};
+use tokio::time::MissedTickBehavior;
+
+use crate::consumers::config::ConsumerConfig;
+use crate::consumers::rdkafka_impl::{consumer_client_config, RebalanceContext};
+use crate::errors::KafkaError;
+use crate::messages::kafka_message::{self, KafkaTopic};
+use crate::KafkaResult;
+
+use super::redriver::{DlqRedriverInterface, RedriveOptions, RedriveReport, RedriveTarget};
+use super::DeadLetterMetadata;
+
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[async_trait]
+impl DlqRedriverInterface for Arc<StreamConsumer<RebalanceContext>> {
+    fn new(
+        consumer_group_id: String,
+        bootstrap_servers: String,
+        config: ConsumerConfig,
+    ) -> KafkaResult<Self> {
+        tracing::info!("Creating DLQ redriver with group ID {}", consumer_group_id);
+        let mut client_config =
+            consumer_client_config(consumer_group_id, bootstrap_servers, &config)?;
+        // The redriver commits the offsets of the records it has redriven itself,
+        // and reads a dead letter queue it has never read before from the start.
+        client_config.set("enable.auto.commit", "false");
+        if config.auto_offset_reset.is_none() {
+            client_config.set("auto.offset.reset", "earliest");
+        }
+        client_config
+            .create_with_context(RebalanceContext::default())
+            .map(Arc::new)
+            .map_err(|e| {
+                tracing::error!("DLQ redriver creation failed: {:?}", e);
+                e.into()
+            })
+    }
+
+    async fn redrive<'a, Target: RedriveTarget>(
+        &'a self,
+        dlq_topic: &'a KafkaTopic,
+        target: &'a Target,
+        options: &'a RedriveOptions,
+    ) -> KafkaResult<RedriveReport> {
+        let topic_name = dlq_topic.name.as_str();
+        let metadata = self.fetch_metadata(Some(topic_name), METADATA_TIMEOUT)?;
+        let mut assignment = TopicPartitionList::new();
+        for partition in metadata
+            .topics()
+            .iter()
+            .flat_map(|topic| topic.partitions().iter())
+        {
+            assignment.add_partition_offset(topic_name, partition.id(), Offset::Stored)?;
+        }
+        let committed = self.committed_offsets(assignment.clone(), METADATA_TIMEOUT)?;
+        // The end of every partition that still has records to redrive, as it was when the redrive started
+        let mut end_offsets = HashMap::new();
+        for element in assignment.elements() {
+            let (low, high) =
+                self.fetch_watermarks(topic_name, element.partition(), METADATA_TIMEOUT)?;
+            let start = match committed
+                .find_partition(topic_name, element.partition())
+                .map(|committed| committed.offset())
+            {
+                Some(Offset::Offset(offset)) => offset.max(low),
+                _ => low,
+            };
+            if start < high {
+                end_offsets.insert(element.partition(), high);
+            }
+        }
+        let mut report = RedriveReport::default();
+        if end_offsets.is_empty() {
+            tracing::info!("Nothing to redrive from {}", topic_name);
+            return Ok(report);
+        }
+        self.assign(&assignment)?;
+        let mut rate_limiter = options.rate_limit.map(|records_per_second| {
+            let mut rate_limiter =
+                tokio::time::interval(Duration::from_secs(1) / records_per_second.max(1));
+            rate_limiter.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            rate_limiter
+        });
+        let mut redriven_offsets = TopicPartitionList::new();
+        let result: KafkaResult<()> = async {
+            while !end_offsets.is_empty() {
+                let event = match tokio::time::timeout(options.idle_timeout, self.recv()).await {
+                    Ok(Ok(event)) => event,
+                    Ok(Err(error)) => {
+                        tracing::error!("Kafka error: {}", error);
+                        continue;
+                    }
+                    Err(_) => {
+                        tracing::warn!(
+                            "No record received from {} for {:?}, stopping the redrive",
+                            topic_name,
+                            options.idle_timeout
+                        );
+                        break;
+                    }
+                };
+                report.scanned += 1;
+                let headers = kafka_message::KafkaMessage::headers(&event).unwrap_or_default();
+                let metadata = DeadLetterMetadata::from_headers(&headers);
+                let event_type = headers.get("ce_type").map(String::as_str);
+                if options.filter.matches(event_type, metadata.as_ref()) {
+                    if options.dry_run {
+                        tracing::info!(
+                            "Would redrive {}[{}] at offset {} ({:?})",
+                            event.topic(),
+                            event.partition(),
+                            event.offset(),
+                            metadata
+                        );
+                    } else {
+                        if let Some(rate_limiter) = rate_limiter.as_mut() {
+                            rate_limiter.tick().await;
+                        }
+                        target.redrive(&event, metadata.as_ref()).await?;
+                    }
+                    report.redriven += 1;
+                } else {
+                    report.skipped += 1;
+                }
+                redriven_offsets.set_partition_offset(
+                    event.topic(),
+                    event.partition(),
+                    Offset::Offset(event.offset() + 1),
+                )?;
+                if !options.dry_run {
+                    commit_offset(self, &event);
+                }
+                if end_offsets
+                    .get(&event.partition())
+                    .is_some_and(|end_offset| event.offset() + 1 >= *end_offset)
+                {
+                    end_offsets.remove(&event.partition());
+                }
+            }
+            Ok(())
+        }
+        .await;
+        // The offsets were committed asynchronously as the records were redriven,
+        // the last ones are waited for before the partitions are unassigned.
+        if !options.dry_run && redriven_offsets.count() > 0 {
+            if let Err(error) = self.commit(&redriven_offsets, CommitMode::Sync) {
+                tracing::error!("dlq::rdkafka_impl::commit::error: {:?}", error);
+            }
+        }
+        self.unassign().map_err(KafkaError::from)?;
+        tracing::info!("Redrive of {} finished: {:?}", topic_name, report);
+        result.map(|()| report)
+    }
+}
+
+/// Commits the offset after the record, without waiting for the broker to acknowledge the commit
+fn commit_offset(consumer: &StreamConsumer<RebalanceContext>, event: &BorrowedMessage<'_>) {
+    let mut offsets = TopicPartitionList::new();
+    let result = offsets
+        .add_partition_offset(
+            event.topic(),
+            event.partition(),
+            Offset::Offset(event.offset() + 1),
+        )
+        .and_then(|()| consumer.commit(&offsets, CommitMode::Async));
+    if let Err(error) = result {
+        tracing::error!("dlq::rdkafka_impl::commit::error: {:?}", error);
+    }
+}
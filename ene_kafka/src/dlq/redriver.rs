@@ -0,0 +1,311 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    consumers::{config::ConsumerConfig, retry_topics::RETRY_HEADER_PREFIX},
+    dispatchers::EventDispatcher,
+    messages::{
+        cloud_events::cloud_event::CloudEvent,
+        kafka_message::{ContentType, Headers, KafkaMessage, KafkaTopic},
+    },
+    producers::producer::{KafkaProducer, KafkaProducerInterface},
+    ConsumerImpl, KafkaResult,
+};
+
+use super::{DeadLetterMetadata, DLQ_HEADER_PREFIX};
+
+#[async_trait]
+pub trait DlqRedriverInterface: Send + Sync {
+    fn new(
+        consumer_group_id: String,
+        bootstrap_servers: String,
+        config: ConsumerConfig,
+    ) -> KafkaResult<Self>
+    where
+        Self: Sized;
+    /// Reads the dead letter queue up to its end at the time of the call and hands every matching record to the target
+    async fn redrive<'a, Target: RedriveTarget>(
+        &'a self,
+        dlq_topic: &'a KafkaTopic,
+        target: &'a Target,
+        options: &'a RedriveOptions,
+    ) -> KafkaResult<RedriveReport>;
+}
+
+///
+/// Where the records of a dead letter queue are sent when they are redriven
+///
+#[async_trait]
+pub trait RedriveTarget: Send + Sync {
+    async fn redrive<Event: CloudEvent<String, String>>(
+        &self,
+        event: &Event,
+        metadata: Option<&DeadLetterMetadata>,
+    ) -> KafkaResult<()>;
+}
+
+/// Republishes the records to the topic they were originally consumed from,
+/// without the headers added by the retry topics and the dead letter queue.
+#[async_trait]
+impl<InnerProducer: KafkaProducerInterface> RedriveTarget for KafkaProducer<InnerProducer> {
+    async fn redrive<Event: CloudEvent<String, String>>(
+        &self,
+        event: &Event,
+        metadata: Option<&DeadLetterMetadata>,
+    ) -> KafkaResult<()> {
+        let metadata = metadata.ok_or(crate::errors::KafkaError::MissingHeader(
+            super::DLQ_ORIGINAL_TOPIC_HEADER.to_string(),
+        ))?;
+        let mut headers = event.headers()?;
+        headers.retain(|key, _| {
+            !key.starts_with(DLQ_HEADER_PREFIX) && !key.starts_with(RETRY_HEADER_PREFIX)
+        });
+        // The content type header is republished with the other headers of the record
+        let content_type = match headers.get("content_type") {
+            Some(content_type) => ContentType::from_str(content_type)?,
+            None => ContentType::Json,
+        };
+        self.send(RedrivenEvent {
+            topic: KafkaTopic {
+                name: metadata.original_topic.clone(),
+                content_type,
+            },
+            key: event.key().ok(),
+            payload: event.payload()?,
+            headers,
+        })
+        .await
+    }
+}
+
+/// Hands the records straight to the handlers of a dispatcher
+struct DispatcherTarget<'a, Dispatcher: EventDispatcher>(&'a Dispatcher);
+
+#[async_trait]
+impl<'a, Dispatcher: EventDispatcher> RedriveTarget for DispatcherTarget<'a, Dispatcher> {
+    async fn redrive<Event: CloudEvent<String, String>>(
+        &self,
+        event: &Event,
+        _metadata: Option<&DeadLetterMetadata>,
+    ) -> KafkaResult<()> {
+        self.0.dispatch_event(event).await
+    }
+}
+
+/// A dead-lettered record on its way back to its original topic, without a key if it had none
+struct RedrivenEvent {
+    topic: KafkaTopic,
+    key: Option<String>,
+    payload: String,
+    headers: Headers,
+}
+
+impl KafkaMessage<Option<String>, String> for RedrivenEvent {
+    fn topic(&self) -> KafkaResult<KafkaTopic> {
+        Ok(self.topic.clone())
+    }
+
+    fn payload(&self) -> KafkaResult<String> {
+        Ok(self.payload.clone())
+    }
+
+    fn key(&self) -> KafkaResult<Option<String>> {
+        Ok(self.key.clone())
+    }
+
+    fn headers(&self) -> KafkaResult<Headers> {
+        Ok(self.headers.clone())
+    }
+}
+
+///
+/// Selects the dead-lettered records that are redriven. Records that do not match are skipped.
+/// An empty filter matches every record.
+///
+#[derive(Debug, Clone, Default)]
+pub struct RedriveFilter {
+    /// Only redrive events of these cloud event types
+    pub event_types: Vec<String>,
+    /// Only redrive events whose error, or one of its sources, contains this text
+    pub error_contains: Option<String>,
+    /// Only redrive events that were dead-lettered at or after this time
+    pub failed_after: Option<DateTime<Utc>>,
+    /// Only redrive events that were dead-lettered before this time
+    pub failed_before: Option<DateTime<Utc>>,
+}
+
+impl RedriveFilter {
+    pub fn with_event_type(mut self, event_type: impl Into<String>) -> Self {
+        self.event_types.push(event_type.into());
+        self
+    }
+
+    pub fn with_error_contains(mut self, error_contains: impl Into<String>) -> Self {
+        self.error_contains = Some(error_contains.into());
+        self
+    }
+
+    pub fn with_failed_after(mut self, failed_after: DateTime<Utc>) -> Self {
+        self.failed_after = Some(failed_after);
+        self
+    }
+
+    pub fn with_failed_before(mut self, failed_before: DateTime<Utc>) -> Self {
+        self.failed_before = Some(failed_before);
+        self
+    }
+
+    /// Returns true if a record with this cloud event type and dead letter metadata should be redriven.
+    /// Records without metadata only match filters that do not look at the error or the failure time.
+    pub fn matches(&self, event_type: Option<&str>, metadata: Option<&DeadLetterMetadata>) -> bool {
+        if !self.event_types.is_empty()
+            && !event_type
+                .is_some_and(|event_type| self.event_types.iter().any(|t| t == event_type))
+        {
+            return false;
+        }
+        if let Some(error_contains) = &self.error_contains {
+            let error_matches = metadata.is_some_and(|metadata| {
+                metadata.error.contains(error_contains.as_str())
+                    || metadata
+                        .error_chain
+                        .iter()
+                        .any(|error| error.contains(error_contains.as_str()))
+            });
+            if !error_matches {
+                return false;
+            }
+        }
+        if self.failed_after.is_none() && self.failed_before.is_none() {
+            return true;
+        }
+        let Some(failed_at) = metadata
+            .and_then(|metadata| DateTime::parse_from_rfc3339(&metadata.failed_at).ok())
+            .map(|failed_at| failed_at.with_timezone(&Utc))
+        else {
+            return false;
+        };
+        self.failed_after
+            .map_or(true, |failed_after| failed_at >= failed_after)
+            && self
+                .failed_before
+                .map_or(true, |failed_before| failed_at < failed_before)
+    }
+}
+
+/// Settings of a redrive run
+#[derive(Debug, Clone)]
+pub struct RedriveOptions {
+    pub filter: RedriveFilter,
+    /// Only report the records that would be redriven, without sending them anywhere or committing any offset
+    pub dry_run: bool,
+    /// The maximum number of records redriven per second
+    pub rate_limit: Option<u32>,
+    /// The redrive stops if no record is received for this long, even if the end of the dead letter queue was not reached
+    pub idle_timeout: Duration,
+}
+
+impl Default for RedriveOptions {
+    fn default() -> Self {
+        Self {
+            filter: RedriveFilter::default(),
+            dry_run: false,
+            rate_limit: None,
+            idle_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// What happened to the records read from the dead letter queue during a redrive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedriveReport {
+    /// The number of records read from the dead letter queue
+    pub scanned: u64,
+    /// The number of records sent to their target, or that would have been in a dry run
+    pub redriven: u64,
+    /// The number of records that did not match the filter
+    pub skipped: u64,
+}
+
+///
+/// Gets events back out of a dead letter queue, e.g. once the bug that made them fail has been fixed.
+/// The redriver reads the dead letter queue with its own consumer group, from its last committed offset up to the end of the queue,
+/// and either republishes the matching records to the topic they were originally consumed from or dispatches them to handlers directly.
+/// The offset of every record is committed once it was redriven or skipped, so an interrupted redrive picks up where it stopped.
+/// The redrive stops at the first record that cannot be redriven and returns its error.
+///
+/// Example:
+/// ```rust,ignore
+/// let redriver = DlqRedriver::new(dlq_topic, "orders-dlq-redrive".to_string(), bootstrap_servers, ConsumerConfig::default())?
+///     .with_filter(RedriveFilter::default().with_event_type("com.ene.entity.created.v1"))
+///     .with_rate_limit(100);
+/// let report = redriver.redrive_to_original_topics(&producer).await?;
+/// ```
+///
+pub struct DlqRedriver<InnerConsumer: DlqRedriverInterface = ConsumerImpl> {
+    dlq_topic: KafkaTopic,
+    inner_consumer: InnerConsumer,
+    options: RedriveOptions,
+}
+
+impl<InnerConsumer: DlqRedriverInterface> DlqRedriver<InnerConsumer> {
+    pub fn new(
+        dlq_topic: KafkaTopic,
+        consumer_group_id: String,
+        bootstrap_servers: String,
+        config: ConsumerConfig,
+    ) -> KafkaResult<Self> {
+        Ok(Self {
+            dlq_topic,
+            inner_consumer: InnerConsumer::new(consumer_group_id, bootstrap_servers, config)?,
+            options: RedriveOptions::default(),
+        })
+    }
+
+    pub fn with_filter(mut self, filter: RedriveFilter) -> Self {
+        self.options.filter = filter;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// Redrives at most `records_per_second` records per second
+    pub fn with_rate_limit(mut self, records_per_second: u32) -> Self {
+        self.options.rate_limit = Some(records_per_second);
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.options.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Republishes the matching records to the topic they were originally consumed from
+    pub async fn redrive_to_original_topics<InnerProducer: KafkaProducerInterface>(
+        &self,
+        producer: &KafkaProducer<InnerProducer>,
+    ) -> KafkaResult<RedriveReport> {
+        self.inner_consumer
+            .redrive(&self.dlq_topic, producer, &self.options)
+            .await
+    }
+
+    /// Dispatches the matching records to the handlers of `dispatcher`
+    pub async fn redrive_to_dispatcher<Dispatcher: EventDispatcher>(
+        &self,
+        dispatcher: &Dispatcher,
+    ) -> KafkaResult<RedriveReport> {
+        self.inner_consumer
+            .redrive(
+                &self.dlq_topic,
+                &DispatcherTarget(dispatcher),
+                &self.options,
+            )
+            .await
+    }
+}
@@ -0,0 +1,101 @@
+use crate::messages::cloud_events::cloud_event::EventType;
+
+///
+/// The errors returned by Ene Kafka.
+/// Errors returned by event handlers are wrapped in `KafkaError::Handler`,
+/// any `anyhow::Error` can be converted into it with the `?` operator.
+///
+#[derive(Debug, thiserror::Error)]
+pub enum KafkaError {
+    #[error("Failed to serialize: {0}")]
+    Serialization(String),
+    #[error("Failed to deserialize: {0}")]
+    Deserialization(String),
+    #[error("{0} header is missing")]
+    MissingHeader(String),
+    #[error("{0} is not a valid UTF-8 string")]
+    InvalidUtf8(String),
+    #[error("Kafka broker error: {0}")]
+    Broker(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("No handler found for event type {0:?}")]
+    NoHandler(EventType),
+    #[error("Handler timed out after {0:?}")]
+    HandlerTimeout(std::time::Duration),
+    #[error("Handler asked to retry after {0:?}")]
+    RetryAfter(std::time::Duration),
+    #[error("Dead-lettered by its handler: {0}")]
+    DeadLetter(String),
+    #[error("Fatal handler error, the consumer is stopped: {0}")]
+    Fatal(String),
+    #[error("Handler error: {0:#}")]
+    Handler(#[from] anyhow::Error),
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+}
+
+impl KafkaError {
+    /// Returns true if the error may go away when the same operation is tried again,
+    /// e.g. a broker timeout or a failing dependency of a handler.
+    /// Errors caused by the event itself, like a missing header or an invalid payload, are not transient.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Broker(_)
+            | Self::Timeout(_)
+            | Self::HandlerTimeout(_)
+            | Self::RetryAfter(_)
+            | Self::Handler(_) => true,
+            Self::Serialization(_)
+            | Self::Deserialization(_)
+            | Self::MissingHeader(_)
+            | Self::InvalidUtf8(_)
+            | Self::NoHandler(_)
+            | Self::DeadLetter(_)
+            | Self::Fatal(_)
+            | Self::Config(_) => false,
+        }
+    }
+
+    /// Returns how long to wait before trying again, if the handler asked for it with `HandlerOutcome::Retry`
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RetryAfter(after) => Some(*after),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "rdkafka")]
+impl From<rdkafka::error::KafkaError> for KafkaError {
+    fn from(error: rdkafka::error::KafkaError) -> Self {
+        if let rdkafka::error::KafkaError::ClientConfig(..)
+        | rdkafka::error::KafkaError::ClientCreation(..) = error
+        {
+            return Self::Config(error.to_string());
+        }
+        match error.rdkafka_error_code() {
+            Some(code) => Self::from(code),
+            None => Self::Broker(error.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "rdkafka")]
+impl From<rdkafka::types::RDKafkaErrorCode> for KafkaError {
+    fn from(code: rdkafka::types::RDKafkaErrorCode) -> Self {
+        use rdkafka::types::RDKafkaErrorCode;
+        match code {
+            RDKafkaErrorCode::OperationTimedOut
+            | RDKafkaErrorCode::MessageTimedOut
+            | RDKafkaErrorCode::RequestTimedOut
+            | RDKafkaErrorCode::TimedOutQueue => Self::Timeout(code.to_string()),
+            // The record itself is rejected, sending it again fails the same way
+            RDKafkaErrorCode::MessageSizeTooLarge
+            | RDKafkaErrorCode::InvalidMessageSize
+            | RDKafkaErrorCode::InvalidMessage
+            | RDKafkaErrorCode::InvalidRecord => Self::Serialization(code.to_string()),
+            _ => Self::Broker(code.to_string()),
+        }
+    }
+}
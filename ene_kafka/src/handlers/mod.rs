@@ -1,6 +1,70 @@
+use std::{future::Future, time::Duration};
+
 use async_trait::async_trait;
 
-use crate::messages::cloud_events::cloud_event::{CloudEvent, DeserializeFrom, EventType};
+use crate::{
+    consumers::retry_topics::RETRY_ORIGINAL_TOPIC_HEADER,
+    dispatchers::routes::route_key,
+    dlq::DLQ_ORIGINAL_TOPIC_HEADER,
+    errors::KafkaError,
+    messages::{
+        cloud_events::cloud_event::{CloudEvent, DeserializeFrom, EventType},
+        kafka_message::Headers,
+    },
+    KafkaResult,
+};
+
+/// Returns the topic the event was originally published to,
+/// even if it is read from one of its retry topics or redriven from a dead letter queue.
+pub fn source_topic<Event: CloudEvent<String, String>>(event: &Event) -> KafkaResult<String> {
+    source_topic_in(event, &event.headers().unwrap_or_default())
+}
+
+/// Same as `source_topic`, with the headers of the event already read
+pub(crate) fn source_topic_in<Event: CloudEvent<String, String>>(
+    event: &Event,
+    headers: &Headers,
+) -> KafkaResult<String> {
+    match headers
+        .get(RETRY_ORIGINAL_TOPIC_HEADER)
+        .or_else(|| headers.get(DLQ_ORIGINAL_TOPIC_HEADER))
+    {
+        Some(topic) => Ok(topic.clone()),
+        None => Ok(event.topic()?.name),
+    }
+}
+
+/// Fails with `KafkaError::HandlerTimeout` if `handling` does not complete within `timeout`.
+/// The handling is cancelled when it times out.
+pub async fn with_timeout<T, Handling: Future<Output = KafkaResult<T>>>(
+    timeout: Option<Duration>,
+    handling: Handling,
+) -> KafkaResult<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, handling)
+            .await
+            .unwrap_or(Err(KafkaError::HandlerTimeout(timeout))),
+        None => handling.await,
+    }
+}
+
+/// The result of an event a batch handler returned no result for, so that it is not committed as handled
+pub fn missing_batch_result() -> KafkaResult<()> {
+    Err(KafkaError::Handler(anyhow::anyhow!(
+        "The batch handler returned no result for the event"
+    )))
+}
+
+/// Returns true if a handler of `handler_event_type` from `handler_topics` accepts events of `event_type` from `topic`
+fn accepts(
+    event_type: &str,
+    topic: &str,
+    handler_event_type: EventType,
+    handler_topics: &[String],
+) -> bool {
+    event_type == handler_event_type
+        && (handler_topics.is_empty() || handler_topics.iter().any(|t| t == topic))
+}
 
 #[async_trait]
 pub trait EventHandler<
@@ -8,16 +72,240 @@ pub trait EventHandler<
     HandlableEvent: CloudEvent<String, String> + DeserializeFrom<String, String, InputEvent>,
 >
 {
-    fn can_handle(&self, event: &InputEvent) -> anyhow::Result<bool> {
-        Ok(event.event_type()? == self.event_type()?)
+    fn can_handle(&self, event: &InputEvent) -> KafkaResult<bool> {
+        let (event_type, topic) = route_key(event)?;
+        self.can_handle_route(event, &event_type, &topic)
+    }
+
+    /// Same as `can_handle`, with the event type and the source topic of the event already read from its headers.
+    /// `generate_event_dispatcher!` calls it instead of `can_handle`: handlers that override `can_handle` override it as well.
+    fn can_handle_route(
+        &self,
+        _event: &InputEvent,
+        event_type: &str,
+        topic: &str,
+    ) -> KafkaResult<bool> {
+        Ok(accepts(
+            event_type,
+            topic,
+            self.event_type()?,
+            &self.topics(),
+        ))
     }
 
-    fn event_type(&self) -> anyhow::Result<EventType>;
+    fn event_type(&self) -> KafkaResult<EventType>;
+
+    /// The topics this handler handles events from. Events from any topic are handled if empty.
+    fn topics(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// How long the handler may take to handle an event, or a batch of events, before it fails with `KafkaError::HandlerTimeout`.
+    /// There is no timeout by default, see also `KafkaConsumer::with_handler_timeout`.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    async fn deserialize_and_handle(&self, event: &InputEvent) -> KafkaResult<()> {
+        let deserialized_event = HandlableEvent::deserialize_from(event)?;
+        self.handle(&deserialized_event).await?.into_result()
+    }
+
+    async fn handle(&self, event: &HandlableEvent) -> KafkaResult<HandlerOutcome>;
+}
+
+///
+/// What the consumer should do with an event once its handler is done with it.
+/// Errors returned by handlers are retried according to the retry policy, outcomes give handlers more control.
+///
+/// Example:
+/// ```rust,ignore
+/// async fn handle_order_created(&self, event: &OrderCreated) -> ene_kafka::KafkaResult<HandlerOutcome> {
+///     if event.order_id.is_empty() {
+///         return Ok(HandlerOutcome::DeadLetter("The order has no id".to_string()));
+///     }
+///     match self.orders.insert(event).await {
+///         Err(InsertError::Throttled { retry_after }) => Ok(HandlerOutcome::Retry(retry_after)),
+///         result => result.map(|_| HandlerOutcome::Ok).map_err(Into::into),
+///     }
+/// }
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandlerOutcome {
+    /// The event was handled
+    Ok,
+    /// The event could not be handled yet. It is retried in place after the given delay, up to
+    /// `RetryPolicy::max_requested_attempts` times whatever the retry predicate of the policy,
+    /// and then goes through the retry topics and the dead letter queue.
+    Retry(Duration),
+    /// The event is committed without being handled
+    Skip,
+    /// The event is sent to the dead letter queue right away, without being retried
+    DeadLetter(String),
+    /// The consumer stops without committing the event, so that it is consumed again once the consumer is restarted.
+    /// `KafkaConsumer::start` returns `KafkaError::Fatal`.
+    Fatal(String),
+}
+
+impl HandlerOutcome {
+    /// Converts the outcome into the result of the dispatch of the event, see `KafkaError::RetryAfter`,
+    /// `KafkaError::DeadLetter` and `KafkaError::Fatal`
+    pub fn into_result(self) -> KafkaResult<()> {
+        match self {
+            HandlerOutcome::Ok => Ok(()),
+            HandlerOutcome::Retry(after) => Err(KafkaError::RetryAfter(after)),
+            HandlerOutcome::Skip => {
+                tracing::info!("Skipping event as requested by its handler");
+                Ok(())
+            }
+            HandlerOutcome::DeadLetter(reason) => Err(KafkaError::DeadLetter(reason)),
+            HandlerOutcome::Fatal(reason) => Err(KafkaError::Fatal(reason)),
+        }
+    }
+}
+
+/// Converts what handler functions return into a `HandlerOutcome`,
+/// so that the functions behind derived handlers can return either `KafkaResult<()>` or `KafkaResult<HandlerOutcome>`.
+pub trait IntoHandlerOutcome {
+    fn into_outcome(self) -> KafkaResult<HandlerOutcome>;
+}
+
+impl IntoHandlerOutcome for KafkaResult<()> {
+    fn into_outcome(self) -> KafkaResult<HandlerOutcome> {
+        self.map(|()| HandlerOutcome::Ok)
+    }
+}
+
+impl IntoHandlerOutcome for KafkaResult<HandlerOutcome> {
+    fn into_outcome(self) -> KafkaResult<HandlerOutcome> {
+        self
+    }
+}
+
+///
+/// Which events of a batch could not be handled, by their index in the batch.
+/// Events that are not reported as failed are considered handled.
+///
+#[derive(Debug, Default)]
+pub struct BatchOutcome {
+    pub failures: Vec<(usize, KafkaError)>,
+}
+
+impl BatchOutcome {
+    /// All the events of the batch were handled
+    pub fn success() -> Self {
+        Self::default()
+    }
+
+    /// Reports the event at `index` in the batch as failed
+    pub fn with_failure(mut self, index: usize, error: KafkaError) -> Self {
+        self.failures.push((index, error));
+        self
+    }
+
+    /// Reports the outcome of the event at `index` in the batch, see `HandlerOutcome`
+    pub fn with_outcome(self, index: usize, outcome: HandlerOutcome) -> Self {
+        match outcome.into_result() {
+            Ok(()) => self,
+            Err(error) => self.with_failure(index, error),
+        }
+    }
+}
+
+///
+/// Handles events of one type in batches, e.g. to insert them in bulk.
+/// The consumer collects the batches by size or time window, see `BatchConfig`.
+///
+/// Events that are reported as failed in the `BatchOutcome` go through the retry policy and the dead letter queue on their own.
+/// If `handle_batch` returns an error, the events of the batch are handled again one at a time
+/// so that only the events that keep failing are dead-lettered.
+///
+#[async_trait]
+pub trait BatchEventHandler<
+    InputEvent: CloudEvent<String, String>,
+    HandlableEvent: CloudEvent<String, String> + DeserializeFrom<String, String, InputEvent>,
+>
+{
+    fn can_handle(&self, event: &InputEvent) -> KafkaResult<bool> {
+        let (event_type, topic) = route_key(event)?;
+        self.can_handle_route(event, &event_type, &topic)
+    }
+
+    /// Same as `can_handle`, with the event type and the source topic of the event already read from its headers.
+    /// `generate_event_dispatcher!` calls it instead of `can_handle`: handlers that override `can_handle` override it as well.
+    fn can_handle_route(
+        &self,
+        _event: &InputEvent,
+        event_type: &str,
+        topic: &str,
+    ) -> KafkaResult<bool> {
+        Ok(accepts(
+            event_type,
+            topic,
+            self.event_type()?,
+            &self.topics(),
+        ))
+    }
+
+    fn event_type(&self) -> KafkaResult<EventType>;
+
+    /// The topics this handler handles events from. Events from any topic are handled if empty.
+    fn topics(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// How long the handler may take to handle an event, or a batch of events, before it fails with `KafkaError::HandlerTimeout`.
+    /// There is no timeout by default, see also `KafkaConsumer::with_handler_timeout`.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
 
-    async fn deserialize_and_handle(&self, event: &InputEvent) -> anyhow::Result<()> {
-        let deserialized_event = HandlableEvent::deserialize_from(&event)?;
-        self.handle(&deserialized_event).await
+    /// Returns the result of every event of the batch, in the same order
+    async fn deserialize_and_handle_batch(&self, events: &[&InputEvent]) -> Vec<KafkaResult<()>> {
+        let mut results = Vec::with_capacity(events.len());
+        let mut deserialized_events = Vec::with_capacity(events.len());
+        let mut indices = Vec::with_capacity(events.len());
+        for (index, event) in events.iter().enumerate() {
+            match HandlableEvent::deserialize_from(event) {
+                Ok(deserialized_event) => {
+                    deserialized_events.push(deserialized_event);
+                    indices.push(index);
+                    results.push(Ok(()));
+                }
+                Err(error) => results.push(Err(error)),
+            }
+        }
+        if deserialized_events.is_empty() {
+            return results;
+        }
+        match self.handle_batch(&deserialized_events).await {
+            Ok(outcome) => {
+                for (batch_index, error) in outcome.failures {
+                    if let Some(index) = indices.get(batch_index) {
+                        results[*index] = Err(error);
+                    }
+                }
+            }
+            Err(error) => {
+                tracing::warn!(
+                    "Batch of {} events failed, handling them one at a time: {:?}",
+                    deserialized_events.len(),
+                    error
+                );
+                for (deserialized_event, index) in deserialized_events.iter().zip(indices) {
+                    results[index] = self
+                        .handle_batch(std::slice::from_ref(deserialized_event))
+                        .await
+                        .and_then(|outcome| match outcome.failures.into_iter().next() {
+                            Some((_, error)) => Err(error),
+                            None => Ok(()),
+                        });
+                }
+            }
+        }
+        results
     }
 
-    async fn handle(&self, event: &HandlableEvent) -> anyhow::Result<()>;
+    async fn handle_batch(&self, events: &[HandlableEvent]) -> KafkaResult<BatchOutcome>;
 }
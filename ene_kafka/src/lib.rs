@@ -3,14 +3,18 @@ use rdkafka::client::DefaultClientContext;
 pub mod admins;
 pub mod consumers;
 pub mod dispatchers;
+pub mod dlq;
+pub mod errors;
 pub mod handlers;
 pub mod messages;
 pub mod producers;
+pub mod security;
 
-pub type KafkaResult<T> = anyhow::Result<T>;
+pub type KafkaResult<T> = Result<T, errors::KafkaError>;
 
 #[cfg(feature = "rdkafka")]
-pub type ConsumerImpl = rdkafka::consumer::StreamConsumer;
+pub type ConsumerImpl =
+    std::sync::Arc<rdkafka::consumer::StreamConsumer<consumers::rdkafka_impl::RebalanceContext>>;
 #[cfg(feature = "rdkafka")]
 pub type ProducerImpl = rdkafka::producer::FutureProducer;
 #[cfg(feature = "rdkafka")]
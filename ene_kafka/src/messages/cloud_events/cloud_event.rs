@@ -1,20 +1,23 @@
 use std::collections::HashMap;
 
-use crate::messages::kafka_message::{Headers, KafkaMessage, ToBytes};
+use crate::{
+    messages::kafka_message::{Headers, KafkaMessage, ToBytes},
+    KafkaResult,
+};
 
 pub trait CloudEvent<Key: ToBytes, Payload: ToBytes>:
     KafkaMessage<Key, Payload> + Sync + Send
 {
-    fn spec_version(&self) -> anyhow::Result<String>;
-    fn event_type(&self) -> anyhow::Result<String>;
-    fn event_source(&self) -> anyhow::Result<String>;
-    fn event_id(&self) -> anyhow::Result<String>;
-    fn event_time(&self) -> anyhow::Result<String>;
-    fn event_content_type(&self) -> anyhow::Result<String>;
+    fn spec_version(&self) -> KafkaResult<String>;
+    fn event_type(&self) -> KafkaResult<String>;
+    fn event_source(&self) -> KafkaResult<String>;
+    fn event_id(&self) -> KafkaResult<String>;
+    fn event_time(&self) -> KafkaResult<String>;
+    fn event_content_type(&self) -> KafkaResult<String>;
 
-    fn entity_event_type() -> anyhow::Result<String>;
+    fn entity_event_type() -> KafkaResult<String>;
 
-    fn cloud_event_headers(&self) -> anyhow::Result<Headers> {
+    fn cloud_event_headers(&self) -> KafkaResult<Headers> {
         Ok(HashMap::from([
             (String::from("ce_specversion"), self.spec_version()?),
             (String::from("ce_type"), self.event_type()?),
@@ -27,7 +30,7 @@ pub trait CloudEvent<Key: ToBytes, Payload: ToBytes>:
 }
 
 pub trait DeserializeFrom<Key: ToBytes, Payload: ToBytes, InputEvent: CloudEvent<Key, Payload>> {
-    fn deserialize_from(event: &InputEvent) -> anyhow::Result<Self>
+    fn deserialize_from(event: &InputEvent) -> KafkaResult<Self>
     where
         Self: Sized;
 }
@@ -1,31 +1,55 @@
 use std::collections::HashMap;
 
-use anyhow::Result;
+use crate::{errors::KafkaError, KafkaResult};
 
 pub type HeaderKey = String;
 pub type HeaderValue = String;
 pub type Headers = HashMap<HeaderKey, HeaderValue>;
 
 pub trait ToBytes {
-    fn to_bytes(&self) -> Result<Vec<u8>>;
+    fn to_bytes(&self) -> KafkaResult<Vec<u8>>;
+
+    /// Returns the bytes to send, or `None` to send a record without a key or a payload
+    fn to_optional_bytes(&self) -> KafkaResult<Option<Vec<u8>>> {
+        self.to_bytes().map(Some)
+    }
 }
 
 impl ToBytes for String {
-    fn to_bytes(&self) -> Result<Vec<u8>> {
+    fn to_bytes(&self) -> KafkaResult<Vec<u8>> {
         Ok(self.as_bytes().to_vec())
     }
 }
 
+impl ToBytes for Vec<u8> {
+    fn to_bytes(&self) -> KafkaResult<Vec<u8>> {
+        Ok(self.clone())
+    }
+}
+
+impl<T: ToBytes> ToBytes for Option<T> {
+    fn to_bytes(&self) -> KafkaResult<Vec<u8>> {
+        Ok(self.to_optional_bytes()?.unwrap_or_default())
+    }
+
+    fn to_optional_bytes(&self) -> KafkaResult<Option<Vec<u8>>> {
+        self.as_ref().map(ToBytes::to_bytes).transpose()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ContentType {
     Json,
 }
 
 impl ContentType {
-    pub fn from_str(content_type: &str) -> Result<Self> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(content_type: &str) -> KafkaResult<Self> {
         match content_type {
-            "json" => Ok(Self::Json),
-            _ => Err(anyhow::anyhow!("Invalid content type")),
+            "json" | "application/json" => Ok(Self::Json),
+            _ => Err(KafkaError::Config(format!(
+                "Invalid content type {content_type}"
+            ))),
         }
     }
 }
@@ -37,8 +61,8 @@ pub struct KafkaTopic {
 }
 
 pub trait KafkaMessage<Key: ToBytes, Payload: ToBytes>: Sync + Send {
-    fn topic(&self) -> anyhow::Result<KafkaTopic>;
-    fn payload(&self) -> anyhow::Result<Payload>;
-    fn key(&self) -> anyhow::Result<Key>;
-    fn headers(&self) -> anyhow::Result<Headers>;
+    fn topic(&self) -> KafkaResult<KafkaTopic>;
+    fn payload(&self) -> KafkaResult<Payload>;
+    fn key(&self) -> KafkaResult<Key>;
+    fn headers(&self) -> KafkaResult<Headers>;
 }
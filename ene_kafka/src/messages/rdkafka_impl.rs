@@ -2,121 +2,192 @@ use super::{
     cloud_events::cloud_event::CloudEvent,
     kafka_message::{ContentType, Headers as KafkaHeaders, KafkaTopic},
 };
-use crate::messages::kafka_message::KafkaMessage;
-use anyhow::anyhow;
+use crate::{errors::KafkaError, messages::kafka_message::KafkaMessage, KafkaResult};
 use rdkafka::{
     message::{BorrowedHeaders, BorrowedMessage, Header, Headers, OwnedHeaders, OwnedMessage},
     Message,
 };
 
 pub trait ToRdkafkaHeaders {
-    fn to_rdkafka_headers(&self) -> anyhow::Result<rdkafka::message::OwnedHeaders>;
+    fn to_rdkafka_headers(&self) -> KafkaResult<rdkafka::message::OwnedHeaders>;
 }
 
 impl<'a> KafkaMessage<String, String> for BorrowedMessage<'a> {
-    fn topic(&self) -> anyhow::Result<KafkaTopic> {
+    fn topic(&self) -> KafkaResult<KafkaTopic> {
         Ok(KafkaTopic {
             name: Message::topic(self).to_string(),
             content_type: ContentType::Json,
         })
     }
 
-    fn payload(&self) -> anyhow::Result<String> {
+    fn payload(&self) -> KafkaResult<String> {
         Message::payload(self)
-            .map(|bytes| -> anyhow::Result<String> { Ok(String::from_utf8(bytes.to_vec())?) })
-            .ok_or(anyhow!("Payload is not a valid UTF-8 string"))?
+            .map(|bytes| {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| KafkaError::InvalidUtf8("Payload".to_string()))
+            })
+            .ok_or(KafkaError::Deserialization(
+                "Message has no payload".to_string(),
+            ))?
     }
 
-    fn key(&self) -> anyhow::Result<String> {
+    fn key(&self) -> KafkaResult<String> {
         Message::key(self)
-            .map(|bytes| -> anyhow::Result<String> { Ok(String::from_utf8(bytes.to_vec())?) })
-            .ok_or(anyhow!("Key is not a valid UTF-8 string"))?
+            .map(|bytes| {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| KafkaError::InvalidUtf8("Key".to_string()))
+            })
+            .ok_or(KafkaError::Deserialization(
+                "Message has no key".to_string(),
+            ))?
     }
 
-    fn headers(&self) -> anyhow::Result<crate::messages::kafka_message::Headers> {
+    fn headers(&self) -> KafkaResult<crate::messages::kafka_message::Headers> {
         Message::headers(self)
             .map(borrowed_headers_to_headers)
-            .ok_or(anyhow!("Headers are not a valid UTF-8 string"))?
+            .ok_or(KafkaError::Deserialization(
+                "Message has no headers".to_string(),
+            ))?
     }
 }
 
 impl KafkaMessage<String, String> for OwnedMessage {
-    fn topic(&self) -> anyhow::Result<KafkaTopic> {
+    fn topic(&self) -> KafkaResult<KafkaTopic> {
         Ok(KafkaTopic {
             name: Message::topic(self).to_string(),
             content_type: ContentType::Json, // TODO: Json is only supported for now
         })
     }
 
-    fn payload(&self) -> anyhow::Result<String> {
+    fn payload(&self) -> KafkaResult<String> {
         Message::payload(self)
-            .map(|bytes| -> anyhow::Result<String> { Ok(String::from_utf8(bytes.to_vec())?) })
-            .ok_or(anyhow!("Payload is not a valid UTF-8 string"))?
+            .map(|bytes| {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| KafkaError::InvalidUtf8("Payload".to_string()))
+            })
+            .ok_or(KafkaError::Deserialization(
+                "Message has no payload".to_string(),
+            ))?
     }
 
-    fn key(&self) -> anyhow::Result<String> {
+    fn key(&self) -> KafkaResult<String> {
         Message::key(self)
-            .map(|bytes| -> anyhow::Result<String> { Ok(String::from_utf8(bytes.to_vec())?) })
-            .ok_or(anyhow!("Key is not a valid UTF-8 string"))?
+            .map(|bytes| {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| KafkaError::InvalidUtf8("Key".to_string()))
+            })
+            .ok_or(KafkaError::Deserialization(
+                "Message has no key".to_string(),
+            ))?
     }
 
-    fn headers(&self) -> anyhow::Result<crate::messages::kafka_message::Headers> {
+    fn headers(&self) -> KafkaResult<crate::messages::kafka_message::Headers> {
         Message::headers(self)
             .map(owned_headers_to_headers)
-            .ok_or(anyhow!("Headers are not a valid UTF-8 string"))?
+            .ok_or(KafkaError::Deserialization(
+                "Message has no headers".to_string(),
+            ))?
     }
 }
 
 impl<'a> CloudEvent<String, String> for BorrowedMessage<'a> {
-    fn spec_version(&self) -> anyhow::Result<String> {
+    fn spec_version(&self) -> KafkaResult<String> {
         KafkaMessage::headers(self)?
             .get("ce_specversion")
-            .map(|value| value.clone())
-            .ok_or(anyhow!("ce_specversion header is missing"))
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_specversion".to_string()))
     }
 
-    fn event_type(&self) -> anyhow::Result<String> {
+    fn event_type(&self) -> KafkaResult<String> {
         KafkaMessage::headers(self)?
             .get("ce_type")
-            .map(|value| value.clone())
-            .ok_or(anyhow!("ce_type header is missing"))
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_type".to_string()))
     }
 
-    fn event_source(&self) -> anyhow::Result<String> {
+    fn event_source(&self) -> KafkaResult<String> {
         KafkaMessage::headers(self)?
             .get("ce_source")
-            .map(|value| value.clone())
-            .ok_or(anyhow!("ce_source header is missing"))
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_source".to_string()))
     }
 
-    fn event_id(&self) -> anyhow::Result<String> {
+    fn event_id(&self) -> KafkaResult<String> {
         KafkaMessage::headers(self)?
             .get("ce_id")
-            .map(|value| value.clone())
-            .ok_or(anyhow!("ce_id header is missing"))
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_id".to_string()))
     }
 
-    fn event_time(&self) -> anyhow::Result<String> {
+    fn event_time(&self) -> KafkaResult<String> {
         KafkaMessage::headers(self)?
             .get("ce_time")
-            .map(|value| value.clone())
-            .ok_or(anyhow!("ce_time header is missing"))
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_time".to_string()))
     }
 
-    fn event_content_type(&self) -> anyhow::Result<String> {
+    fn event_content_type(&self) -> KafkaResult<String> {
         KafkaMessage::headers(self)?
             .get("content_type")
-            .map(|value| value.clone())
-            .ok_or(anyhow!("content_type header is missing"))
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("content_type".to_string()))
     }
 
-    fn entity_event_type() -> anyhow::Result<String> {
+    fn entity_event_type() -> KafkaResult<String> {
         Ok(String::from("lib.rdkafka.BorrowedMessage"))
     }
 }
 
+impl CloudEvent<String, String> for OwnedMessage {
+    fn spec_version(&self) -> KafkaResult<String> {
+        KafkaMessage::headers(self)?
+            .get("ce_specversion")
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_specversion".to_string()))
+    }
+
+    fn event_type(&self) -> KafkaResult<String> {
+        KafkaMessage::headers(self)?
+            .get("ce_type")
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_type".to_string()))
+    }
+
+    fn event_source(&self) -> KafkaResult<String> {
+        KafkaMessage::headers(self)?
+            .get("ce_source")
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_source".to_string()))
+    }
+
+    fn event_id(&self) -> KafkaResult<String> {
+        KafkaMessage::headers(self)?
+            .get("ce_id")
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_id".to_string()))
+    }
+
+    fn event_time(&self) -> KafkaResult<String> {
+        KafkaMessage::headers(self)?
+            .get("ce_time")
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("ce_time".to_string()))
+    }
+
+    fn event_content_type(&self) -> KafkaResult<String> {
+        KafkaMessage::headers(self)?
+            .get("content_type")
+            .cloned()
+            .ok_or(KafkaError::MissingHeader("content_type".to_string()))
+    }
+
+    fn entity_event_type() -> KafkaResult<String> {
+        Ok(String::from("lib.rdkafka.OwnedMessage"))
+    }
+}
+
 impl ToRdkafkaHeaders for KafkaHeaders {
-    fn to_rdkafka_headers(&self) -> anyhow::Result<rdkafka::message::OwnedHeaders> {
+    fn to_rdkafka_headers(&self) -> KafkaResult<rdkafka::message::OwnedHeaders> {
         let mut owned_headers = rdkafka::message::OwnedHeaders::new();
         for (key, value) in self.iter() {
             let header = Header {
@@ -129,36 +200,38 @@ impl ToRdkafkaHeaders for KafkaHeaders {
     }
 }
 
-pub fn borrowed_headers_to_headers(headers: &BorrowedHeaders) -> anyhow::Result<KafkaHeaders> {
+pub fn borrowed_headers_to_headers(headers: &BorrowedHeaders) -> KafkaResult<KafkaHeaders> {
     headers
         .iter()
         .filter(|header| header.value.is_some())
-        .map(|header| -> anyhow::Result<(String, String)> {
+        .map(|header| -> KafkaResult<(String, String)> {
             let key = header.key.to_string();
             let value = String::from_utf8(
                 header
                     .value
-                    .ok_or(anyhow!("header with key {key} not avaiable"))?
+                    .ok_or(KafkaError::MissingHeader(key.clone()))?
                     .to_vec(),
-            )?;
+            )
+            .map_err(|_| KafkaError::InvalidUtf8(format!("Header {key}")))?;
             Ok((key, value))
         })
-        .collect::<anyhow::Result<KafkaHeaders>>()
+        .collect::<KafkaResult<KafkaHeaders>>()
 }
 
-pub fn owned_headers_to_headers(headers: &OwnedHeaders) -> anyhow::Result<KafkaHeaders> {
+pub fn owned_headers_to_headers(headers: &OwnedHeaders) -> KafkaResult<KafkaHeaders> {
     headers
         .iter()
         .filter(|header| header.value.is_some())
-        .map(|header| -> anyhow::Result<(String, String)> {
+        .map(|header| -> KafkaResult<(String, String)> {
             let key = header.key.to_string();
             let value = String::from_utf8(
                 header
                     .value
-                    .ok_or(anyhow!("header with key {key} not avaiable"))?
+                    .ok_or(KafkaError::MissingHeader(key.clone()))?
                     .to_vec(),
-            )?;
+            )
+            .map_err(|_| KafkaError::InvalidUtf8(format!("Header {key}")))?;
             Ok((key, value))
         })
-        .collect::<anyhow::Result<KafkaHeaders>>()
+        .collect::<KafkaResult<KafkaHeaders>>()
 }
@@ -0,0 +1,154 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::security::SecurityConfig;
+
+/// The number of acknowledgements the leader broker must receive before a produce request is considered complete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Acks {
+    None,
+    Leader,
+    All,
+}
+
+impl Acks {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "0",
+            Self::Leader => "1",
+            Self::All => "all",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompressionCodec {
+    None,
+    Gzip,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl CompressionCodec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Gzip => "gzip",
+            Self::Snappy => "snappy",
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+///
+/// Tuning options of a Kafka producer.
+/// Every option that is left empty falls back to the default of the underlying Kafka client.
+/// `properties` are passed as-is to the underlying client and take precedence over the typed options.
+///
+/// The config is built by chaining the `with_*` methods on top of the defaults.
+///
+/// Example:
+/// ```rust,ignore
+/// // A producer that favours throughput over latency
+/// let config = ProducerConfig::default()
+///     .with_linger(Duration::from_millis(50))
+///     .with_batch_size(1_000_000)
+///     .with_compression(CompressionCodec::Lz4);
+///
+/// // A producer that favours latency and delivery guarantees
+/// let config = ProducerConfig::default()
+///     .with_linger(Duration::ZERO)
+///     .with_idempotence(true);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct ProducerConfig {
+    pub acks: Option<Acks>,
+    pub linger: Option<Duration>,
+    pub batch_size: Option<u32>,
+    pub compression: Option<CompressionCodec>,
+    pub enable_idempotence: Option<bool>,
+    pub retries: Option<u32>,
+    pub message_timeout: Option<Duration>,
+    pub request_timeout: Option<Duration>,
+    pub client_id: Option<String>,
+    pub security: SecurityConfig,
+    pub properties: HashMap<String, String>,
+}
+
+impl Default for ProducerConfig {
+    fn default() -> Self {
+        Self {
+            acks: Some(Acks::All),
+            linger: None,
+            batch_size: None,
+            compression: None,
+            enable_idempotence: None,
+            retries: None,
+            message_timeout: Some(Duration::from_millis(5000)),
+            request_timeout: None,
+            client_id: None,
+            security: SecurityConfig::default(),
+            properties: HashMap::new(),
+        }
+    }
+}
+
+impl ProducerConfig {
+    pub fn with_acks(mut self, acks: Acks) -> Self {
+        self.acks = Some(acks);
+        self
+    }
+
+    pub fn with_linger(mut self, linger: Duration) -> Self {
+        self.linger = Some(linger);
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: CompressionCodec) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn with_idempotence(mut self, enable_idempotence: bool) -> Self {
+        self.enable_idempotence = Some(enable_idempotence);
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    pub fn with_message_timeout(mut self, message_timeout: Duration) -> Self {
+        self.message_timeout = Some(message_timeout);
+        self
+    }
+
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn with_security(mut self, security: SecurityConfig) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Sets a raw property on the underlying Kafka client, e.g. `queue.buffering.max.messages`
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+}
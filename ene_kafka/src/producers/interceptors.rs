@@ -0,0 +1,145 @@
+use std::{fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+
+use crate::{
+    messages::kafka_message::{Headers, KafkaMessage, KafkaTopic, ToBytes},
+    KafkaResult,
+};
+
+/// What an interceptor knows about the message being sent. Interceptors can change its topic and its headers.
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    pub topic: KafkaTopic,
+    pub headers: Headers,
+    key: Option<Vec<u8>>,
+}
+
+impl OutgoingMessage {
+    fn from_message<Key: ToBytes, Payload: ToBytes, Message: KafkaMessage<Key, Payload>>(
+        message: &Message,
+    ) -> KafkaResult<Self> {
+        Ok(Self {
+            topic: message.topic()?,
+            headers: message.headers()?,
+            key: message.key()?.to_optional_bytes()?,
+        })
+    }
+
+    /// The serialized key of the message, `None` if it is sent without a key
+    pub fn key(&self) -> Option<&[u8]> {
+        self.key.as_deref()
+    }
+}
+
+/// The rest of the interceptor chain, ending with the producer sending the message
+pub struct Next<'a> {
+    run: Box<dyn FnOnce(OutgoingMessage) -> BoxFuture<'a, KafkaResult<()>> + Send + 'a>,
+}
+
+impl<'a> Next<'a> {
+    fn new(
+        run: impl FnOnce(OutgoingMessage) -> BoxFuture<'a, KafkaResult<()>> + Send + 'a,
+    ) -> Self {
+        Self { run: Box::new(run) }
+    }
+
+    /// Runs the next interceptors and sends the message, and returns the result of the send
+    pub async fn run(self, message: OutgoingMessage) -> KafkaResult<()> {
+        (self.run)(message).await
+    }
+}
+
+///
+/// Runs around every message sent by a `KafkaProducer`, e.g. to propagate a tenant or a trace id in the headers.
+/// An interceptor calls `next.run(message)` to continue sending the message, or returns without calling it
+/// to short-circuit the send. The message passed to `next.run` is the one that is sent, with the changes of the interceptor.
+/// Interceptors run in the order they were added to the producer, the first one being the outermost.
+///
+/// Example:
+/// ```rust,ignore
+/// #[derive(Debug)]
+/// struct TenantPropagator;
+///
+/// #[async_trait]
+/// impl ProducerInterceptor for TenantPropagator {
+///     async fn send(&self, mut message: OutgoingMessage, next: Next<'_>) -> KafkaResult<()> {
+///         message.headers.insert("tenant".to_string(), current_tenant());
+///         next.run(message).await
+///     }
+/// }
+///
+/// let producer = kafka_producer!(bootstrap_servers = "localhost:9092".to_string())?
+///     .with_interceptor(TenantPropagator);
+/// ```
+///
+#[async_trait]
+pub trait ProducerInterceptor: Debug + Send + Sync {
+    async fn send(&self, message: OutgoingMessage, next: Next<'_>) -> KafkaResult<()>;
+}
+
+pub(crate) async fn intercept<'a, Key, Payload, Message>(
+    interceptors: &'a [Arc<dyn ProducerInterceptor>],
+    message: Message,
+    send: impl FnOnce(InterceptedMessage<Message>) -> BoxFuture<'a, KafkaResult<()>> + Send + 'a,
+) -> KafkaResult<()>
+where
+    Key: ToBytes + 'a,
+    Payload: ToBytes + 'a,
+    Message: KafkaMessage<Key, Payload> + 'a,
+{
+    let outgoing = OutgoingMessage::from_message(&message)?;
+    let send = Next::new(move |outgoing: OutgoingMessage| {
+        send(InterceptedMessage {
+            message,
+            topic: outgoing.topic,
+            headers: outgoing.headers,
+        })
+    });
+    run_chain(interceptors, outgoing, send).await
+}
+
+fn run_chain<'a>(
+    interceptors: &'a [Arc<dyn ProducerInterceptor>],
+    message: OutgoingMessage,
+    send: Next<'a>,
+) -> BoxFuture<'a, KafkaResult<()>> {
+    match interceptors.split_first() {
+        Some((interceptor, rest)) => interceptor.send(
+            message,
+            Next::new(move |message| run_chain(rest, message, send)),
+        ),
+        None => Box::pin(send.run(message)),
+    }
+}
+
+/// A message with the topic and the headers set by the interceptors
+pub(crate) struct InterceptedMessage<Message> {
+    message: Message,
+    topic: KafkaTopic,
+    headers: Headers,
+}
+
+impl<Key, Payload, Message> KafkaMessage<Key, Payload> for InterceptedMessage<Message>
+where
+    Key: ToBytes,
+    Payload: ToBytes,
+    Message: KafkaMessage<Key, Payload>,
+{
+    fn topic(&self) -> KafkaResult<KafkaTopic> {
+        Ok(self.topic.clone())
+    }
+
+    fn payload(&self) -> KafkaResult<Payload> {
+        self.message.payload()
+    }
+
+    fn key(&self) -> KafkaResult<Key> {
+        self.message.key()
+    }
+
+    fn headers(&self) -> KafkaResult<Headers> {
+        Ok(self.headers.clone())
+    }
+}
@@ -1,2 +1,4 @@
+pub mod config;
+pub mod interceptors;
 pub mod producer;
 pub mod rdkafka_impl;
@@ -1,10 +1,14 @@
 extern crate proc_macro;
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 
 use crate::{
     messages::kafka_message::{KafkaMessage, ToBytes},
-    ProducerImpl,
+    producers::config::ProducerConfig,
+    producers::interceptors::{intercept, ProducerInterceptor},
+    KafkaResult, ProducerImpl,
 };
 
 #[async_trait]
@@ -12,14 +16,25 @@ pub trait KafkaProducerInterface: Sync + Send {
     async fn send<Key: ToBytes, Payload: ToBytes, Message: KafkaMessage<Key, Payload>>(
         &self,
         message: Message,
-    ) -> anyhow::Result<()>
+    ) -> KafkaResult<()>
 where;
-    fn new(bootstrap_servers: String) -> Self;
+    fn new(bootstrap_servers: String, config: ProducerConfig) -> KafkaResult<Self>
+    where
+        Self: Sized;
 }
 
 #[derive(Debug, Clone)]
 pub struct KafkaProducer<Producer: KafkaProducerInterface = ProducerImpl> {
     producer: Producer,
+    interceptors: Vec<Arc<dyn ProducerInterceptor>>,
+}
+
+impl<Producer: KafkaProducerInterface> KafkaProducer<Producer> {
+    /// Runs every message sent by the producer through the interceptor, after the interceptors added before it
+    pub fn with_interceptor(mut self, interceptor: impl ProducerInterceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
 }
 
 #[async_trait]
@@ -44,7 +59,7 @@ impl<A: KafkaProducerInterface> KafkaProducerInterface for KafkaProducer<A> {
     ///     pub organisation_id: i64,
     /// }
     ///
-    /// let producer = kafka_producer!(bootstrap_servers = "localhost:9092".to_string());
+    /// let producer = kafka_producer!(bootstrap_servers = "localhost:9092".to_string())?;
     /// let event = EntityCreated {
     ///     entity_id: 1,
     ///     organisation_id: 1,
@@ -53,32 +68,96 @@ impl<A: KafkaProducerInterface> KafkaProducerInterface for KafkaProducer<A> {
     async fn send<Key: ToBytes, Payload: ToBytes, Message: KafkaMessage<Key, Payload>>(
         &self,
         message: Message,
-    ) -> anyhow::Result<()> {
+    ) -> KafkaResult<()> {
         tracing::debug!("sending message");
-        self.producer.send(message).await
+        if self.interceptors.is_empty() {
+            return self.producer.send(message).await;
+        }
+        intercept::<Key, Payload, _>(&self.interceptors, message, |message| {
+            self.producer.send::<Key, Payload, _>(message)
+        })
+        .await
     }
 
-    fn new(bootstrap_servers: String) -> Self {
-        Self {
-            producer: A::new(bootstrap_servers),
-        }
+    fn new(bootstrap_servers: String, config: ProducerConfig) -> KafkaResult<Self> {
+        Ok(Self {
+            producer: A::new(bootstrap_servers, config)?,
+            interceptors: Vec::new(),
+        })
     }
 }
 
 ///
 /// Create a new Kafka producer
+/// Returns an error if the producer could not be created, e.g. because of an invalid configuration.
 /// Arguments:
 /// - `bootstrap_servers` - a string representing the Kafka bootstrap servers
+/// - `config` - (optional) a `ProducerConfig` to tune the producer. The defaults are used if omitted.
+/// - `acks`, `linger`, `batch_size`, `compression`, `idempotence`, `retries`, `message_timeout`, `request_timeout`,
+///   `client_id` and `security` - (optional) set one option of the `ProducerConfig`, see its `with_*` functions.
+///   They are applied in order, on top of `config` if it comes first.
 ///
 /// Example:
 /// ```rust, ignore
-/// let producer = kafka_producer!(bootstrap_servers = "localhost:9092".to_string());
+/// let producer = kafka_producer!(bootstrap_servers = "localhost:9092".to_string())?;
 /// producer.send(event).await?;
+///
+/// let producer = kafka_producer!(
+///     bootstrap_servers = "localhost:9092".to_string(),
+///     acks = Acks::All,
+///     idempotence = true,
+///     compression = CompressionCodec::Lz4,
+///     linger = Duration::from_millis(5)
+/// )?;
+///
+/// let producer = kafka_producer!(
+///     bootstrap_servers = "localhost:9092".to_string(),
+///     config = ProducerConfig::default().with_compression(CompressionCodec::Lz4)
+/// )?;
 /// ```
 ///
 #[macro_export]
 macro_rules! kafka_producer {
-    (bootstrap_servers = $bootstrap_servers: expr) => {
-        <ene_kafka::producers::producer::KafkaProducer>::new($bootstrap_servers)
+    (bootstrap_servers = $bootstrap_servers: expr $(, $setting: ident = $value: expr)* $(,)?) => {
+        {
+            let config = ene_kafka::producers::config::ProducerConfig::default();
+            $(
+                let config = ene_kafka::kafka_producer!(@setting config, $setting = $value);
+            )*
+            <ene_kafka::producers::producer::KafkaProducer>::new($bootstrap_servers, config)
+        }
+    };
+    (@setting $config: ident, config = $value: expr) => {
+        $value
+    };
+    (@setting $config: ident, acks = $value: expr) => {
+        $config.with_acks($value)
+    };
+    (@setting $config: ident, linger = $value: expr) => {
+        $config.with_linger($value)
+    };
+    (@setting $config: ident, batch_size = $value: expr) => {
+        $config.with_batch_size($value)
+    };
+    (@setting $config: ident, compression = $value: expr) => {
+        $config.with_compression($value)
+    };
+    (@setting $config: ident, idempotence = $value: expr) => {
+        $config.with_idempotence($value)
+    };
+    (@setting $config: ident, retries = $value: expr) => {
+        $config.with_retries($value)
+    };
+    (@setting $config: ident, message_timeout = $value: expr) => {
+        $config.with_message_timeout($value)
+    };
+    (@setting $config: ident, request_timeout = $value: expr) => {
+        $config.with_request_timeout($value)
+    };
+    (@setting $config: ident, client_id = $value: expr) => {
+        $config.with_client_id($value)
+    };
+    (@setting $config: ident, security = $value: expr) => {
+        $config.with_security($value)
     };
 }
@@ -5,44 +5,99 @@ use rdkafka::{
     ClientConfig,
 };
 
-use crate::messages::{
-    kafka_message::{KafkaMessage, ToBytes},
-    rdkafka_impl::ToRdkafkaHeaders,
+use crate::{
+    messages::{
+        kafka_message::{KafkaMessage, ToBytes},
+        rdkafka_impl::ToRdkafkaHeaders,
+    },
+    security::rdkafka_impl::apply_security_config,
+    KafkaResult,
 };
 
-use super::producer::KafkaProducerInterface;
+use super::{config::ProducerConfig, producer::KafkaProducerInterface};
+
+fn producer_client_config(
+    bootstrap_servers: String,
+    config: &ProducerConfig,
+) -> KafkaResult<ClientConfig> {
+    let mut client_config = ClientConfig::new();
+    client_config.set("bootstrap.servers", bootstrap_servers);
+    if let Some(acks) = &config.acks {
+        client_config.set("request.required.acks", acks.as_str());
+    }
+    if let Some(linger) = config.linger {
+        client_config.set("linger.ms", linger.as_millis().to_string());
+    }
+    if let Some(batch_size) = config.batch_size {
+        client_config.set("batch.size", batch_size.to_string());
+    }
+    if let Some(compression) = &config.compression {
+        client_config.set("compression.codec", compression.as_str());
+    }
+    if let Some(enable_idempotence) = config.enable_idempotence {
+        client_config.set("enable.idempotence", enable_idempotence.to_string());
+    }
+    if let Some(retries) = config.retries {
+        client_config.set("retries", retries.to_string());
+    }
+    if let Some(message_timeout) = config.message_timeout {
+        client_config.set(
+            "message.timeout.ms",
+            message_timeout.as_millis().to_string(),
+        );
+    }
+    if let Some(request_timeout) = config.request_timeout {
+        client_config.set(
+            "request.timeout.ms",
+            request_timeout.as_millis().to_string(),
+        );
+    }
+    if let Some(client_id) = &config.client_id {
+        client_config.set("client.id", client_id);
+    }
+    apply_security_config(&config.security, &mut client_config)?;
+    for (key, value) in config.properties.iter() {
+        client_config.set(key, value);
+    }
+    Ok(client_config)
+}
 
 #[async_trait]
 impl KafkaProducerInterface for FutureProducer {
     async fn send<Key: ToBytes, Payload: ToBytes, Message: KafkaMessage<Key, Payload>>(
         &self,
         message: Message,
-    ) -> anyhow::Result<()> {
-        let payload = message.payload()?.to_bytes()?;
-        let key = message.key()?.to_bytes()?;
+    ) -> KafkaResult<()> {
+        let payload = message.payload()?.to_optional_bytes()?;
+        let key = message.key()?.to_optional_bytes()?;
         let topic = message.topic()?;
         let record: FutureRecord<'_, Vec<u8>, Vec<u8>> = FutureRecord::<Vec<u8>, Vec<u8>> {
             topic: topic.name.as_str(),
             partition: None,
-            payload: Some(&payload),
-            key: Some(&key),
+            payload: payload.as_ref(),
+            key: key.as_ref(),
             timestamp: None,
             headers: Some(message.headers()?.to_rdkafka_headers()?),
         };
         let delivery_status = FutureProducer::send(self, record, Timeout::Never).await;
         match delivery_status {
             Ok(_) => Ok(()),
-            Err(e) => Err(anyhow::anyhow!(format!("Failed to produce event: {:?}", e))),
+            Err((e, _)) => {
+                tracing::error!("Failed to produce event: {:?}", e);
+                Err(e.into())
+            }
         }
     }
 
-    fn new(bootstrap_servers: String) -> Self {
-        // TODO: configure
-        ClientConfig::new()
-            .set("request.required.acks", "all")
-            .set("bootstrap.servers", bootstrap_servers)
-            .set("message.timeout.ms", "5000")
+    fn new(bootstrap_servers: String, config: ProducerConfig) -> KafkaResult<Self> {
+        producer_client_config(bootstrap_servers, &config)?
             .create()
-            .expect("producers::rdkafka_impl - failed to create producer")
+            .map_err(|e| {
+                tracing::error!(
+                    "producers::rdkafka_impl - failed to create producer: {:?}",
+                    e
+                );
+                e.into()
+            })
     }
 }
@@ -0,0 +1,237 @@
+pub mod rdkafka_impl;
+
+use std::{fmt::Debug, path::PathBuf};
+
+use crate::{errors::KafkaError, KafkaResult};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SecurityProtocol {
+    #[default]
+    Plaintext,
+    Ssl,
+    SaslPlaintext,
+    SaslSsl,
+}
+
+impl SecurityProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plaintext => "PLAINTEXT",
+            Self::Ssl => "SSL",
+            Self::SaslPlaintext => "SASL_PLAINTEXT",
+            Self::SaslSsl => "SASL_SSL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaslMechanism {
+    Plain,
+    ScramSha256,
+    ScramSha512,
+    OAuthBearer,
+}
+
+impl SaslMechanism {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plain => "PLAIN",
+            Self::ScramSha256 => "SCRAM-SHA-256",
+            Self::ScramSha512 => "SCRAM-SHA-512",
+            Self::OAuthBearer => "OAUTHBEARER",
+        }
+    }
+}
+
+///
+/// A sensitive value that is resolved when the client is created.
+/// - `Value` - the secret itself
+/// - `File` - the path of a file containing the secret. Surrounding whitespace is trimmed.
+/// - `Env` - the name of an environment variable containing the secret
+///
+/// The value of a secret is never printed by its `Debug` implementation.
+///
+#[derive(Clone, PartialEq, Eq)]
+pub enum Secret {
+    Value(String),
+    File(PathBuf),
+    Env(String),
+}
+
+impl Secret {
+    pub fn resolve(&self) -> KafkaResult<String> {
+        match self {
+            Self::Value(value) => Ok(value.clone()),
+            Self::File(path) => std::fs::read_to_string(path)
+                .map(|value| value.trim().to_string())
+                .map_err(|e| {
+                    KafkaError::Config(format!(
+                        "Failed to read secret from {}: {}",
+                        path.display(),
+                        e
+                    ))
+                }),
+            Self::Env(name) => std::env::var(name).map_err(|e| {
+                KafkaError::Config(format!(
+                    "Failed to read secret from env var {}: {}",
+                    name, e
+                ))
+            }),
+        }
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Value(_) => write!(f, "Value(***)"),
+            Self::File(path) => write!(f, "File({:?})", path),
+            Self::Env(name) => write!(f, "Env({:?})", name),
+        }
+    }
+}
+
+/// Settings of the OIDC token retrieval used by the `OAUTHBEARER` mechanism
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthBearerConfig {
+    pub client_id: String,
+    pub client_secret: Secret,
+    pub token_endpoint_url: String,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaslConfig {
+    pub mechanism: SaslMechanism,
+    pub username: Option<Secret>,
+    pub password: Option<Secret>,
+    pub oauthbearer: Option<OAuthBearerConfig>,
+}
+
+impl SaslConfig {
+    pub fn plain(username: Secret, password: Secret) -> Self {
+        Self {
+            mechanism: SaslMechanism::Plain,
+            username: Some(username),
+            password: Some(password),
+            oauthbearer: None,
+        }
+    }
+
+    pub fn scram_sha_256(username: Secret, password: Secret) -> Self {
+        Self {
+            mechanism: SaslMechanism::ScramSha256,
+            username: Some(username),
+            password: Some(password),
+            oauthbearer: None,
+        }
+    }
+
+    pub fn scram_sha_512(username: Secret, password: Secret) -> Self {
+        Self {
+            mechanism: SaslMechanism::ScramSha512,
+            username: Some(username),
+            password: Some(password),
+            oauthbearer: None,
+        }
+    }
+
+    pub fn oauthbearer(oauthbearer: OAuthBearerConfig) -> Self {
+        Self {
+            mechanism: SaslMechanism::OAuthBearer,
+            username: None,
+            password: None,
+            oauthbearer: Some(oauthbearer),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SslConfig {
+    pub ca_location: Option<PathBuf>,
+    pub certificate_location: Option<PathBuf>,
+    pub key_location: Option<PathBuf>,
+    pub key_password: Option<Secret>,
+    /// Verify the broker hostname against its certificate. Enabled by default in the underlying client.
+    pub endpoint_identification: Option<bool>,
+}
+
+impl SslConfig {
+    pub fn with_ca_location(mut self, ca_location: impl Into<PathBuf>) -> Self {
+        self.ca_location = Some(ca_location.into());
+        self
+    }
+
+    pub fn with_certificate_location(mut self, certificate_location: impl Into<PathBuf>) -> Self {
+        self.certificate_location = Some(certificate_location.into());
+        self
+    }
+
+    pub fn with_key_location(mut self, key_location: impl Into<PathBuf>) -> Self {
+        self.key_location = Some(key_location.into());
+        self
+    }
+
+    pub fn with_key_password(mut self, key_password: Secret) -> Self {
+        self.key_password = Some(key_password);
+        self
+    }
+
+    pub fn with_endpoint_identification(mut self, endpoint_identification: bool) -> Self {
+        self.endpoint_identification = Some(endpoint_identification);
+        self
+    }
+}
+
+///
+/// How a client authenticates with and encrypts its connection to the Kafka brokers.
+/// The same config can be shared by admins, consumers and producers.
+///
+/// Example:
+/// ```rust,ignore
+/// let security = SecurityConfig::sasl_ssl(
+///     SaslConfig::scram_sha_512(
+///         Secret::Env("KAFKA_USERNAME".to_string()),
+///         Secret::File("/var/run/secrets/kafka/password".into()),
+///     ),
+///     SslConfig::default().with_ca_location("/etc/ssl/certs/ca.pem"),
+/// );
+/// let config = ConsumerConfig::default().with_security(security);
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SecurityConfig {
+    pub protocol: SecurityProtocol,
+    pub sasl: Option<SaslConfig>,
+    pub ssl: Option<SslConfig>,
+}
+
+impl SecurityConfig {
+    pub fn plaintext() -> Self {
+        Self::default()
+    }
+
+    pub fn ssl(ssl: SslConfig) -> Self {
+        Self {
+            protocol: SecurityProtocol::Ssl,
+            sasl: None,
+            ssl: Some(ssl),
+        }
+    }
+
+    pub fn sasl_plaintext(sasl: SaslConfig) -> Self {
+        Self {
+            protocol: SecurityProtocol::SaslPlaintext,
+            sasl: Some(sasl),
+            ssl: None,
+        }
+    }
+
+    pub fn sasl_ssl(sasl: SaslConfig, ssl: SslConfig) -> Self {
+        Self {
+            protocol: SecurityProtocol::SaslSsl,
+            sasl: Some(sasl),
+            ssl: Some(ssl),
+        }
+    }
+}
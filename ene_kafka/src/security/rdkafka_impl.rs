@@ -0,0 +1,67 @@
+use rdkafka::ClientConfig;
+
+use super::{SaslMechanism, SecurityConfig};
+use crate::KafkaResult;
+
+/// Sets the security related properties of `config` on an rdkafka client config, resolving all secrets
+pub(crate) fn apply_security_config(
+    config: &SecurityConfig,
+    client_config: &mut ClientConfig,
+) -> KafkaResult<()> {
+    client_config.set("security.protocol", config.protocol.as_str());
+    if let Some(sasl) = &config.sasl {
+        client_config.set("sasl.mechanisms", sasl.mechanism.as_str());
+        if let Some(username) = &sasl.username {
+            client_config.set("sasl.username", username.resolve()?);
+        }
+        if let Some(password) = &sasl.password {
+            client_config.set("sasl.password", password.resolve()?);
+        }
+        if let (SaslMechanism::OAuthBearer, Some(oauthbearer)) =
+            (&sasl.mechanism, &sasl.oauthbearer)
+        {
+            client_config
+                .set("sasl.oauthbearer.method", "oidc")
+                .set("sasl.oauthbearer.client.id", &oauthbearer.client_id)
+                .set(
+                    "sasl.oauthbearer.client.secret",
+                    oauthbearer.client_secret.resolve()?,
+                )
+                .set(
+                    "sasl.oauthbearer.token.endpoint.url",
+                    &oauthbearer.token_endpoint_url,
+                );
+            if let Some(scope) = &oauthbearer.scope {
+                client_config.set("sasl.oauthbearer.scope", scope);
+            }
+        }
+    }
+    if let Some(ssl) = &config.ssl {
+        if let Some(ca_location) = &ssl.ca_location {
+            client_config.set("ssl.ca.location", ca_location.to_string_lossy());
+        }
+        if let Some(certificate_location) = &ssl.certificate_location {
+            client_config.set(
+                "ssl.certificate.location",
+                certificate_location.to_string_lossy(),
+            );
+        }
+        if let Some(key_location) = &ssl.key_location {
+            client_config.set("ssl.key.location", key_location.to_string_lossy());
+        }
+        if let Some(key_password) = &ssl.key_password {
+            client_config.set("ssl.key.password", key_password.resolve()?);
+        }
+        if let Some(endpoint_identification) = ssl.endpoint_identification {
+            client_config.set(
+                "ssl.endpoint.identification.algorithm",
+                if endpoint_identification {
+                    "https"
+                } else {
+                    "none"
+                },
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use syn::DeriveInput;
+
+#[derive(deluxe::ExtractAttributes)]
+#[deluxe(attributes(batch_event_handler))]
+struct BatchHandlerAttributes {
+    event: syn::ExprPath,
+    handler: syn::Ident,
+    #[deluxe(default)]
+    topics: Vec<String>,
+    #[deluxe(default)]
+    timeout_ms: Option<u64>,
+}
+
+pub fn batch_handler_derive_macro2(
+    input: proc_macro2::TokenStream,
+) -> deluxe::Result<proc_macro2::TokenStream> {
+    let mut ast: DeriveInput = syn::parse2(input)?;
+    let BatchHandlerAttributes {
+        event,
+        handler,
+        topics,
+        timeout_ms,
+    }: BatchHandlerAttributes = deluxe::extract_attributes(&mut ast)?;
+    let struct_name = &ast.ident;
+
+    let event_path = event.path;
+    let topics_fn = (!topics.is_empty()).then(|| {
+        quote::quote! {
+            fn topics(&self) -> Vec<String> {
+                vec![#(#topics.to_string()),*]
+            }
+        }
+    });
+    let timeout_fn = timeout_ms.map(|timeout_ms| {
+        quote::quote! {
+            fn timeout(&self) -> Option<std::time::Duration> {
+                Some(std::time::Duration::from_millis(#timeout_ms))
+            }
+        }
+    });
+
+    Ok(quote::quote! {
+        #[async_trait::async_trait]
+        impl<InputEvent: ene_kafka::messages::cloud_events::cloud_event::CloudEvent<String, String>> BatchEventHandler<InputEvent, #event_path> for #struct_name {
+            fn event_type(&self) -> ene_kafka::KafkaResult<ene_kafka::messages::cloud_events::cloud_event::EventType> {
+                use ene_kafka::messages::cloud_events::cloud_event::CloudEvent;
+                #event_path::entity_event_type()
+            }
+
+            #topics_fn
+
+            #timeout_fn
+
+            async fn handle_batch(&self, events: &[#event_path]) -> ene_kafka::KafkaResult<ene_kafka::handlers::BatchOutcome> {
+                #struct_name::#handler(self, events).await
+            }
+        }
+    })
+}
@@ -14,7 +14,7 @@ struct CloudEventAttributes {
 pub fn cloudevent_derive_macro2(
     input: proc_macro2::TokenStream,
 ) -> deluxe::Result<proc_macro2::TokenStream> {
-    let mut ast: DeriveInput = syn::parse2(input.into())?;
+    let mut ast: DeriveInput = syn::parse2(input)?;
     let CloudEventAttributes {
         content_type,
         version,
@@ -5,7 +5,7 @@ use crate::kafka_message::KafkaMessageAttributes;
 pub fn deserialize_from_derive_macro2(
     input: proc_macro2::TokenStream,
 ) -> deluxe::Result<proc_macro2::TokenStream> {
-    let mut ast: DeriveInput = syn::parse2(input.into())?;
+    let mut ast: DeriveInput = syn::parse2(input)?;
     let KafkaMessageAttributes { serde, .. }: KafkaMessageAttributes =
         deluxe::extract_attributes(&mut ast)?;
     let struct_name = &ast.ident;
@@ -15,7 +15,7 @@ pub fn deserialize_from_derive_macro2(
             fn deserialize_from(value: &Event) -> ene_kafka::KafkaResult<Self> {
                 match ene_kafka::messages::kafka_message::ContentType::#serde {
                     ene_kafka::messages::kafka_message::ContentType::Json => {
-                        Ok(serde_json::from_str::<#struct_name>(value.payload()?.as_str())?)
+                        serde_json::from_str::<#struct_name>(value.payload()?.as_str()).map_err(|e| ene_kafka::errors::KafkaError::Deserialization(e.to_string()))
                     }
                 }
             }
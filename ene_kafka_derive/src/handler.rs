@@ -5,17 +5,39 @@ use syn::DeriveInput;
 struct HandlerAttributes {
     event: syn::ExprPath,
     handler: syn::Ident,
+    #[deluxe(default)]
+    topics: Vec<String>,
+    #[deluxe(default)]
+    timeout_ms: Option<u64>,
 }
 
 pub fn handler_derive_macro2(
     input: proc_macro2::TokenStream,
 ) -> deluxe::Result<proc_macro2::TokenStream> {
-    let mut ast: DeriveInput = syn::parse2(input.into())?;
-    let HandlerAttributes { event, handler }: HandlerAttributes =
-        deluxe::extract_attributes(&mut ast)?;
+    let mut ast: DeriveInput = syn::parse2(input)?;
+    let HandlerAttributes {
+        event,
+        handler,
+        topics,
+        timeout_ms,
+    }: HandlerAttributes = deluxe::extract_attributes(&mut ast)?;
     let struct_name = &ast.ident;
 
     let event_path = event.path;
+    let topics_fn = (!topics.is_empty()).then(|| {
+        quote::quote! {
+            fn topics(&self) -> Vec<String> {
+                vec![#(#topics.to_string()),*]
+            }
+        }
+    });
+    let timeout_fn = timeout_ms.map(|timeout_ms| {
+        quote::quote! {
+            fn timeout(&self) -> Option<std::time::Duration> {
+                Some(std::time::Duration::from_millis(#timeout_ms))
+            }
+        }
+    });
 
     Ok(quote::quote! {
         #[async_trait::async_trait]
@@ -25,8 +47,12 @@ pub fn handler_derive_macro2(
                 #event_path::entity_event_type()
             }
 
-            async fn handle(&self, event: &#event_path) -> ene_kafka::KafkaResult<()> {
-                #struct_name::#handler(self, event).await
+            #topics_fn
+
+            #timeout_fn
+
+            async fn handle(&self, event: &#event_path) -> ene_kafka::KafkaResult<ene_kafka::handlers::HandlerOutcome> {
+                ene_kafka::handlers::IntoHandlerOutcome::into_outcome(#struct_name::#handler(self, event).await)
             }
         }
     })
@@ -39,7 +39,7 @@ pub fn kafkamessage_derive_macro2(
     input: proc_macro2::TokenStream,
 ) -> deluxe::Result<proc_macro2::TokenStream> {
     // convert into an ast
-    let mut ast: DeriveInput = syn::parse2((input).into())?;
+    let mut ast: DeriveInput = syn::parse2(input)?;
 
     // extract struct attributes
     let KafkaMessageAttributes {
@@ -77,7 +77,7 @@ pub fn kafkamessage_derive_macro2(
             }
 
             fn payload(&self) -> ene_kafka::KafkaResult<String> {
-                serde_json::to_string(self).map_err(|e| anyhow::anyhow!("Failed to serialize payload: {}", e))
+                serde_json::to_string(self).map_err(|e| ene_kafka::errors::KafkaError::Serialization(e.to_string()))
             }
 
             fn key(&self) -> ene_kafka::KafkaResult<String> {
@@ -1,3 +1,4 @@
+mod batch_handler;
 mod cloud_event;
 mod deserialize_from;
 mod handler;
@@ -60,6 +61,8 @@ pub fn cloudevent_derive_macro(input: proc_macro::TokenStream) -> proc_macro::To
 /// It requires the following attributes:
 /// - `event` - A concrete type that implements the `CloudEvent` trait
 /// - `handler` - The name of the handler function. This function should be implemented by the struct. It should take a reference to the event it can handle as input.
+/// - `topics` - (optional) The topics the handler handles events from, e.g. `topics = ["orders"]`. Events from any topic are handled if omitted.
+/// - `timeout_ms` - (optional) How long the handler may take, in milliseconds, before it fails with `KafkaError::HandlerTimeout`.
 ///
 /// The event type should implement `CloudEvent` as well as `DeserializeFrom` is required for this trait to work.
 /// Example:
@@ -69,7 +72,7 @@ pub fn cloudevent_derive_macro(input: proc_macro::TokenStream) -> proc_macro::To
 /// struct SomeEventHandler;
 ///
 /// impl SomeEventHandler {
-///   async fn handle_some_event(&self, event: &crate::SomeEvent) -> anyhow::Result<()> {
+///   async fn handle_some_event(&self, event: &crate::SomeEvent) -> ene_kafka::KafkaResult<()> {
 ///    println!("Handling event: {:?}", event);
 ///    Ok(())
 ///  }
@@ -80,9 +83,38 @@ pub fn handler_derive_macro(input: proc_macro::TokenStream) -> proc_macro::Token
     handler::handler_derive_macro2(input.into()).unwrap().into()
 }
 
+/// Derive the BatchEventHandler trait for a struct
+/// It requires the following attributes:
+/// - `event` - A concrete type that implements the `CloudEvent` trait
+/// - `handler` - The name of the batch handler function. This function should be implemented by the struct. It should take a slice of the events it can handle as input and return a `BatchOutcome`.
+/// - `topics` - (optional) The topics the handler handles events from. Events from any topic are handled if omitted.
+/// - `timeout_ms` - (optional) How long the handler may take, in milliseconds, before it fails with `KafkaError::HandlerTimeout`.
+///
+/// The event type should implement `CloudEvent` as well as `DeserializeFrom` is required for this trait to work.
+/// Example:
+/// ```rust,ignore
+/// #[derive(BatchEventHandler)]
+/// #[batch_event_handler(event = crate::SomeEvent, handler = handle_some_events)]
+/// struct SomeEventBatchHandler;
+///
+/// impl SomeEventBatchHandler {
+///   async fn handle_some_events(&self, events: &[crate::SomeEvent]) -> ene_kafka::KafkaResult<BatchOutcome> {
+///    println!("Handling {} events", events.len());
+///    Ok(BatchOutcome::success())
+///  }
+/// }
+/// ```
+#[proc_macro_derive(BatchEventHandler, attributes(batch_event_handler))]
+pub fn batch_handler_derive_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    batch_handler::batch_handler_derive_macro2(input.into())
+        .unwrap()
+        .into()
+}
+
 /// Derive the DeserializeFrom trait for a struct
 /// It relies on the `KafkaMessage` trait and requires the following attributes:
 /// - `serde` - the serialization format of the payload. Possible values: `Json`
+///
 /// `DeserializeFrom` requires the struct to implement `Deserialize` from the `serde` crate.
 ///
 /// Example:
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use ene_kafka::errors::KafkaError;
 use ene_kafka::messages::cloud_events::cloud_event::{CloudEvent, DeserializeFrom};
 use ene_kafka::producers::producer::KafkaProducerInterface;
 use ene_kafka::{kafka_producer, producers::producer::KafkaProducer};
@@ -21,9 +22,8 @@ struct EntityCreated {
 
 impl<Event: CloudEvent<String, String>> DeserializeFrom<String, String, Event> for EntityCreated {
     fn deserialize_from(value: &Event) -> ene_kafka::KafkaResult<Self> {
-        Ok(serde_json::from_str::<EntityCreated>(
-            value.payload()?.as_str(),
-        )?)
+        serde_json::from_str::<EntityCreated>(value.payload()?.as_str())
+            .map_err(|e| KafkaError::Deserialization(e.to_string()))
     }
 }
 
@@ -34,7 +34,7 @@ async fn main() -> ene_kafka::KafkaResult<()> {
         .init();
     let bootstrap_servers = "localhost:9092".to_string();
 
-    let producer: KafkaProducer = kafka_producer!(bootstrap_servers = bootstrap_servers.clone());
+    let producer: KafkaProducer = kafka_producer!(bootstrap_servers = bootstrap_servers.clone())?;
     let event = EntityCreated {
         entity_id: 1755,
         organisation_id: 42,
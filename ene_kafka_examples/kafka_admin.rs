@@ -16,7 +16,7 @@ async fn main() -> ene_kafka::KafkaResult<()> {
         bootstrap_servers = bootstrap_servers,
         request_time_out_ms = "50000".to_string(),
         connection_max_idle_ms = "0".to_string()
-    );
+    )?;
 
     let is_topic_live = admin.check_topic_liveness("test").await?;
     println!("Is topic live: {}", is_topic_live);
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use ene_kafka::consumers::config::{AutoOffsetReset, ConsumerConfig};
+use ene_kafka::consumers::options::BatchConfig;
+use ene_kafka::handlers::BatchOutcome;
+use ene_kafka::messages::kafka_message::ContentType;
+use serde::{Deserialize, Serialize};
+
+use ene_kafka::kafka_consumer;
+use ene_kafka::{
+    handlers::{BatchEventHandler, EventHandler},
+    messages::kafka_message::KafkaTopic,
+};
+use ene_kafka_derive::{
+    BatchEventHandler, CloudEvent, DeserializeFrom, EventHandler, KafkaMessage,
+};
+
+#[derive(KafkaMessage, Serialize, CloudEvent, Debug, Deserialize, DeserializeFrom)]
+#[kafka(topic = "test", serde = Json, key = entity_id, headers = CloudEvent)]
+#[cloud_event(
+    content_type = "application/json",
+    version = "1.0",
+    event_type = "com.ene.entity.created.v1",
+    event_source = "https://ene-kafka.com/docs/cloudevents/entity/created",
+    id = entity_id
+)]
+struct EntityCreated {
+    pub entity_id: i64,
+    pub organisation_id: i64,
+}
+
+#[derive(KafkaMessage, Serialize, CloudEvent, Debug, Deserialize, DeserializeFrom)]
+#[kafka(topic = "test", serde = Json, key = entity_id, headers = CloudEvent)]
+#[cloud_event(
+    content_type = "application/json",
+    version = "1.0",
+    event_type = "com.ene.entity.updated.v1",
+    event_source = "https://ene-kafka.com/docs/cloudevents/entity/updated",
+    id = entity_id
+)]
+struct EntityUpdated {
+    pub entity_id: i64,
+    pub organisation_id: i64,
+}
+
+#[tokio::main]
+async fn main() -> ene_kafka::KafkaResult<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+    let bootstrap_servers = "localhost:9092".to_string();
+
+    let consumer = kafka_consumer!(
+        topic = KafkaTopic {
+            name: "test".to_string(),
+            content_type: ContentType::Json
+        },
+        dlq_topic = KafkaTopic {
+            name: "test-dlq".to_string(),
+            content_type: ContentType::Json
+        },
+        consumer_group_id = "test-batch-group",
+        bootstrap_servers = bootstrap_servers,
+        config = ConsumerConfig::default().with_auto_offset_reset(AutoOffsetReset::Earliest),
+        handlers = {
+            entity_updated_event_handler: EntityUpdatedHandler = EntityUpdatedHandler {}
+        },
+        batch_handlers = {
+            entity_created_batch_handler: EntityCreatedBatchHandler = EntityCreatedBatchHandler {}
+        }
+    )?
+    .with_batching(BatchConfig::new(100, Duration::from_millis(500)));
+
+    let handle = consumer.handle();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            handle.shutdown();
+        }
+    });
+    consumer.start().await
+}
+
+#[derive(BatchEventHandler)]
+#[batch_event_handler(event = EntityCreated, handler = handle_entity_created_events)]
+struct EntityCreatedBatchHandler {}
+
+impl EntityCreatedBatchHandler {
+    async fn handle_entity_created_events(
+        &self,
+        events: &[EntityCreated],
+    ) -> ene_kafka::KafkaResult<BatchOutcome> {
+        println!("EntityCreatedBatchHandler: {} events", events.len());
+        let mut outcome = BatchOutcome::success();
+        for (index, event) in events.iter().enumerate() {
+            if event.organisation_id < 0 {
+                outcome = outcome.with_failure(
+                    index,
+                    anyhow::anyhow!("Invalid organisation {}", event.organisation_id).into(),
+                );
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+#[derive(EventHandler)]
+#[event_handler(event = EntityUpdated, handler = handle_entity_updated_event)]
+struct EntityUpdatedHandler {}
+
+impl EntityUpdatedHandler {
+    async fn handle_entity_updated_event(
+        &self,
+        event: &EntityUpdated,
+    ) -> ene_kafka::KafkaResult<()> {
+        println!("EntityUpdatedHandler: {:?}", event);
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use ene_kafka::consumers::config::{AutoOffsetReset, ConsumerConfig};
+use ene_kafka::consumers::retry::RetryPolicy;
 use ene_kafka::messages::kafka_message::ContentType;
 use serde::{Deserialize, Serialize};
 
@@ -51,14 +55,25 @@ async fn main() -> ene_kafka::KafkaResult<()> {
         },
         consumer_group_id = "test-group",
         bootstrap_servers = bootstrap_servers,
+        config = ConsumerConfig::default().with_auto_offset_reset(AutoOffsetReset::Earliest),
         handlers = {
             entity_created_event_handler: EntityCreatedEventHandler = EntityCreatedEventHandler {},
             entity_updated_event_handler: EntityUpdatedHandler = EntityUpdatedHandler {}
         }
-    );
-    consumer.start().await;
+    )?
+    .with_retry_policy(RetryPolicy::exponential(
+        3,
+        Duration::from_millis(100),
+        Duration::from_secs(5),
+    ));
 
-    Ok(())
+    let handle = consumer.handle();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            handle.shutdown();
+        }
+    });
+    consumer.start().await
 }
 
 #[derive(EventHandler)]
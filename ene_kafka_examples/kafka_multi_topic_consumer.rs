@@ -0,0 +1,98 @@
+use ene_kafka::kafka_consumer;
+use ene_kafka::messages::kafka_message::ContentType;
+use serde::{Deserialize, Serialize};
+
+use ene_kafka::{handlers::EventHandler, messages::kafka_message::KafkaTopic};
+use ene_kafka_derive::{CloudEvent, DeserializeFrom, EventHandler, KafkaMessage};
+
+#[derive(KafkaMessage, Serialize, CloudEvent, Debug, Deserialize, DeserializeFrom)]
+#[kafka(topic = "orders", serde = Json, key = order_id, headers = CloudEvent)]
+#[cloud_event(
+    content_type = "application/json",
+    version = "1.0",
+    event_type = "com.ene.order.created.v1",
+    event_source = "https://ene-kafka.com/docs/cloudevents/order/created",
+    id = order_id
+)]
+struct OrderCreated {
+    pub order_id: i64,
+    pub amount: i64,
+}
+
+#[derive(KafkaMessage, Serialize, CloudEvent, Debug, Deserialize, DeserializeFrom)]
+#[kafka(topic = "payments", serde = Json, key = payment_id, headers = CloudEvent)]
+#[cloud_event(
+    content_type = "application/json",
+    version = "1.0",
+    event_type = "com.ene.payment.received.v1",
+    event_source = "https://ene-kafka.com/docs/cloudevents/payment/received",
+    id = payment_id
+)]
+struct PaymentReceived {
+    pub payment_id: i64,
+    pub order_id: i64,
+}
+
+fn json_topic(name: &str) -> KafkaTopic {
+    KafkaTopic {
+        name: name.to_string(),
+        content_type: ContentType::Json,
+    }
+}
+
+#[tokio::main]
+async fn main() -> ene_kafka::KafkaResult<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+    let bootstrap_servers = "localhost:9092".to_string();
+
+    // The failed events of every topic go to their own dead letter queue,
+    // `billing-dlq` is the dead letter queue of the topics that have none of their own.
+    let consumer = kafka_consumer!(
+        topics = [
+            json_topic("orders") => json_topic("orders-dlq"),
+            json_topic("payments") => json_topic("payments-dlq"),
+        ],
+        dlq_topic = json_topic("billing-dlq"),
+        consumer_group_id = "billing-group",
+        bootstrap_servers = bootstrap_servers,
+        handlers = {
+            order_created_event_handler: OrderCreatedEventHandler = OrderCreatedEventHandler {},
+            payment_received_event_handler: PaymentReceivedEventHandler = PaymentReceivedEventHandler {}
+        }
+    )?;
+
+    let handle = consumer.handle();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            handle.shutdown();
+        }
+    });
+    consumer.start().await
+}
+
+#[derive(EventHandler)]
+#[event_handler(event = OrderCreated, handler = handle_order_created_event)]
+struct OrderCreatedEventHandler {}
+
+impl OrderCreatedEventHandler {
+    async fn handle_order_created_event(&self, event: &OrderCreated) -> ene_kafka::KafkaResult<()> {
+        println!("OrderCreatedEventHandler: {:?}", event);
+        Ok(())
+    }
+}
+
+#[derive(EventHandler)]
+#[event_handler(event = PaymentReceived, handler = handle_payment_received_event)]
+struct PaymentReceivedEventHandler {}
+
+impl PaymentReceivedEventHandler {
+    async fn handle_payment_received_event(
+        &self,
+        event: &PaymentReceived,
+    ) -> ene_kafka::KafkaResult<()> {
+        println!("PaymentReceivedEventHandler: {:?}", event);
+        Ok(())
+    }
+}
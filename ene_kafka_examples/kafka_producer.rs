@@ -1,5 +1,8 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use ene_kafka::producers::config::Acks;
 use ene_kafka::producers::producer::KafkaProducerInterface;
 use ene_kafka::{kafka_producer, producers::producer::KafkaProducer};
 use ene_kafka_derive::{CloudEvent, DeserializeFrom, KafkaMessage};
@@ -25,7 +28,12 @@ async fn main() -> ene_kafka::KafkaResult<()> {
         .init();
     let bootstrap_servers = "localhost:9092".to_string();
 
-    let producer: KafkaProducer = kafka_producer!(bootstrap_servers = bootstrap_servers.clone());
+    let producer: KafkaProducer = kafka_producer!(
+        bootstrap_servers = bootstrap_servers.clone(),
+        acks = Acks::All,
+        idempotence = true,
+        linger = Duration::from_millis(5)
+    )?;
     let event = EntityUpdated {
         entity_id: 1755,
         organisation_id: 42,
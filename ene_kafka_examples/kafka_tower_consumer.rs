@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use ene_kafka::consumers::config::{AutoOffsetReset, ConsumerConfig};
+use ene_kafka::dispatchers::service::DispatcherService;
+use ene_kafka::messages::kafka_message::ContentType;
+use serde::{Deserialize, Serialize};
+use tower::ServiceBuilder;
+
+use ene_kafka::{generate_event_dispatcher, kafka_consumer};
+use ene_kafka::{handlers::EventHandler, messages::kafka_message::KafkaTopic};
+use ene_kafka_derive::{CloudEvent, DeserializeFrom, EventHandler, KafkaMessage};
+
+#[derive(KafkaMessage, Serialize, CloudEvent, Debug, Deserialize, DeserializeFrom)]
+#[kafka(topic = "test", serde = Json, key = entity_id, headers = CloudEvent)]
+#[cloud_event(
+    content_type = "application/json",
+    version = "1.0",
+    event_type = "com.ene.entity.created.v1",
+    event_source = "https://ene-kafka.com/docs/cloudevents/entity/created",
+    id = entity_id
+)]
+struct EntityCreated {
+    pub entity_id: i64,
+    pub organisation_id: i64,
+}
+
+#[tokio::main]
+async fn main() -> ene_kafka::KafkaResult<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+    let bootstrap_servers = "localhost:9092".to_string();
+
+    generate_event_dispatcher!(entity_created_event_handler: EntityCreatedEventHandler);
+    let service = ServiceBuilder::new()
+        .load_shed()
+        .concurrency_limit(64)
+        .timeout(Duration::from_secs(5))
+        .service(DispatcherService::new(CloudEventDispatcher::new(
+            EntityCreatedEventHandler {},
+        )?));
+
+    let consumer = kafka_consumer!(
+        topic = KafkaTopic {
+            name: "test".to_string(),
+            content_type: ContentType::Json
+        },
+        dlq_topic = KafkaTopic {
+            name: "test-dlq".to_string(),
+            content_type: ContentType::Json
+        },
+        consumer_group_id = "test-tower-group",
+        bootstrap_servers = bootstrap_servers,
+        config = ConsumerConfig::default().with_auto_offset_reset(AutoOffsetReset::Earliest),
+        service = service
+    )?;
+
+    let handle = consumer.handle();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            handle.shutdown();
+        }
+    });
+    consumer.start().await
+}
+
+#[derive(EventHandler)]
+#[event_handler(event = EntityCreated, handler = handle_entity_created_event)]
+struct EntityCreatedEventHandler {}
+
+impl EntityCreatedEventHandler {
+    async fn handle_entity_created_event(
+        &self,
+        event: &EntityCreated,
+    ) -> ene_kafka::KafkaResult<()> {
+        println!("EntityCreatedEventHandler: {:?}", event);
+        Ok(())
+    }
+}